@@ -1,10 +1,65 @@
 pub const SEED_GLOBAL: &[u8] = b"global_state";
 pub const SEED_POOL: &[u8] = b"collateral_pool";
 pub const SEED_VAULT: &[u8] = b"user_vault";
+pub const SEED_VAULT_COUNTER: &[u8] = b"user_vault_counter";
 pub const SEED_POOL_REGISTRY: &[u8] = b"pool_registry";
 pub const SEED_MOCK_PRICE: &[u8] = b"mock_price_feed";
+pub const SEED_TREASURY: &[u8] = b"treasury";
+pub const SEED_TREASURY_AUTHORITY: &[u8] = b"treasury_authority";
+pub const SEED_RESERVE: &[u8] = b"collateral_reserve";
+pub const SEED_AUCTION: &[u8] = b"auction";
+pub const SEED_STABILITY_POOL: &[u8] = b"stability_pool";
+pub const SEED_STABILITY_POOL_STABLE: &[u8] = b"stability_pool_stable";
+pub const SEED_STABILITY_POOL_COLLATERAL: &[u8] = b"stability_pool_collateral";
+pub const SEED_STABILITY_DEPOSIT: &[u8] = b"stability_deposit";
+pub const SEED_SORTED_VAULTS: &[u8] = b"sorted_vaults";
+pub const SEED_PSM: &[u8] = b"psm";
+pub const SEED_PSM_RESERVE: &[u8] = b"psm_reserve";
+pub const SEED_SAVINGS: &[u8] = b"savings";
+pub const SEED_SAVINGS_MINT: &[u8] = b"savings_mint";
+pub const SEED_SAVINGS_STABLE: &[u8] = b"savings_stable";
+pub const SEED_CROSS_MARGIN: &[u8] = b"cross_margin";
 
 pub const BASIS_POINTS_DIVISOR: u64 = 10_000;           // 100% = 10,000 bps
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+pub const PRICE_DECIMALS: u32 = 8;                      // oracle prices are normalized to 8 decimals
+pub const STABLE_DECIMALS: u32 = 6;                     // WUSD decimals
 pub const MAX_POOLS: usize = 10;
 pub const MAX_STABILITY_FEE_BPS: u64 = 10_000;          // Max 100%
-pub const MAX_LIQUIDATION_PENALTY_BPS: u64 = 5_000;     // Max 50%
\ No newline at end of file
+pub const MAX_LIQUIDATION_PENALTY_BPS: u64 = 5_000;     // Max 50%
+pub const DEFAULT_CLOSE_FACTOR_BPS: u64 = 5_000;        // 50% of a vault's debt per liquidation
+pub const DEFAULT_DEBT_DUST: u64 = 10_000_000;          // 10 WUSD minimum vault debt
+pub const MIN_STABILITY_POOL_DEPOSITS: u64 = 1_000_000; // 1 WUSD always left in a stability pool
+pub const MAX_STABILITY_POOL_SCALES: usize = 8;
+pub const MAX_SORTED_VAULTS: usize = 200;               // keeps SortedVaults under the 10KB CPI init limit
+pub const MAX_REDEMPTION_FEE_BPS: u64 = 1_000;          // Max 10%
+pub const MAX_PSM_FEE_BPS: u64 = 1_000;                 // Max 10%
+pub const MAX_FLASH_MINT_FEE_BPS: u64 = 100;            // Max 1%
+pub const MAX_SAVINGS_RATE_BPS: u64 = 2_000;            // Max 20%
+pub const MAX_CROSS_MARGIN_POSITIONS: usize = 5;
+
+// AuctionConfig::curve
+pub const AUCTION_CURVE_LINEAR: u8 = 0;                 // price falls linearly to zero over `duration`
+pub const AUCTION_CURVE_STAIRSTEP: u8 = 1;              // price is cut by `cut_bps` every `step` seconds
+
+// GlobalState::paused_operations flags
+pub const PAUSE_DEPOSIT: u32 = 1 << 0;
+pub const PAUSE_WITHDRAW: u32 = 1 << 1;
+pub const PAUSE_MINT: u32 = 1 << 2;
+pub const PAUSE_REPAY: u32 = 1 << 3;
+pub const PAUSE_LIQUIDATE: u32 = 1 << 4;
+pub const PAUSE_STABILITY_POOL: u32 = 1 << 5;
+pub const PAUSE_REDEEM: u32 = 1 << 6;
+pub const PAUSE_PSM: u32 = 1 << 7;
+pub const PAUSE_FLASH_MINT: u32 = 1 << 8;
+pub const PAUSE_SAVINGS: u32 = 1 << 9;
+pub const PAUSE_ALL: u32 = PAUSE_DEPOSIT
+    | PAUSE_WITHDRAW
+    | PAUSE_MINT
+    | PAUSE_REPAY
+    | PAUSE_LIQUIDATE
+    | PAUSE_STABILITY_POOL
+    | PAUSE_REDEEM
+    | PAUSE_PSM
+    | PAUSE_FLASH_MINT
+    | PAUSE_SAVINGS;
\ No newline at end of file
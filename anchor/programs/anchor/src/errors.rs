@@ -14,6 +14,8 @@ pub enum StableError {
     VaultNotFound,
     #[msg("Pool not active")]
     PoolNotActive,
+    #[msg("Pool is in reduce-only mode")]
+    PoolReduceOnly,
     #[msg("Math overflow")]
     Overflow,
     #[msg("Vault not empty")]
@@ -36,8 +38,72 @@ pub enum StableError {
     OracleConfidenceLow,
     #[msg("Invalid oracle account")]
     InvalidOracle,
+    #[msg("Mock oracle feeds are disabled in this build")]
+    MockOracleDisabled,
     #[msg("Repay amount exceeds debt")]
     RepayAmountExceedsDebt,
     #[msg("Liquidation amount too high")]
     LiquidationAmountTooHigh,
+    #[msg("Operation is paused")]
+    OperationPaused,
+    #[msg("System is shut down")]
+    SystemShutdown,
+    #[msg("System is not shut down")]
+    SystemNotShutdown,
+    #[msg("Pool is already caged")]
+    PoolCaged,
+    #[msg("Pool is not caged")]
+    PoolNotCaged,
+    #[msg("Vault debt must be settled first")]
+    VaultNotSettled,
+    #[msg("Settlement is not finalized")]
+    SettlementNotFinalized,
+    #[msg("Settlement is already finalized")]
+    SettlementAlreadyFinalized,
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasuryBalance,
+    #[msg("Vault collateral still covers its debt")]
+    VaultNotUnderwater,
+    #[msg("Auctions are not configured for this pool")]
+    AuctionNotConfigured,
+    #[msg("Auction price curve has run out, reset the auction")]
+    AuctionNeedsReset,
+    #[msg("Auction is still running")]
+    AuctionStillRunning,
+    #[msg("Auction price is above the bidder's limit")]
+    AuctionPriceTooHigh,
+    #[msg("Stability pool has run out of scales")]
+    StabilityPoolScaleExhausted,
+    #[msg("Redemption fee is above the redeemer's limit")]
+    RedemptionFeeTooHigh,
+    #[msg("No vault could be redeemed against")]
+    NothingToRedeem,
+    #[msg("PSM debt ceiling reached")]
+    PsmDebtCeilingReached,
+    #[msg("Insufficient PSM reserves")]
+    InsufficientPsmReserves,
+    #[msg("Flash mint amount is above the cap")]
+    FlashMintCapExceeded,
+    #[msg("Flash mint must be followed by a matching flash_repay")]
+    FlashRepayMissing,
+    #[msg("A flash mint is already outstanding")]
+    FlashMintOutstanding,
+    #[msg("Flash repay does not match the outstanding flash mint")]
+    FlashRepayMismatch,
+    #[msg("Flash mint cannot be called through CPI")]
+    FlashMintCpiNotAllowed,
+    #[msg("Cross-margin account has no room for another pool")]
+    MaxCrossMarginPositionsReached,
+    #[msg("New vaults must use the next free vault index")]
+    InvalidVaultIndex,
+    #[msg("Pool still holds collateral, debt or stability pool deposits")]
+    PoolNotEmpty,
+    #[msg("Stability pool token accounts are required while it can absorb debt")]
+    StabilityPoolAccountsMissing,
+    #[msg("Vault debt would be below the pool's minimum")]
+    DebtBelowDust,
+    #[msg("Pool must be retired before it can be closed")]
+    PoolStillActive,
+    #[msg("Account already uses the current layout")]
+    AccountAlreadyMigrated,
 }
\ No newline at end of file
@@ -8,6 +8,49 @@ pub struct GlobalStateInitialized {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct GlobalParamsUpdated {
+    pub admin: Pubkey,
+    pub old_debt_ceiling: u64,
+    pub new_debt_ceiling: u64,
+    pub old_stability_fee: u64,
+    pub new_stability_fee: u64,
+    pub old_liquidation_penalty: u64,
+    pub new_liquidation_penalty: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RolesUpdated {
+    pub admin: Pubkey,
+    pub risk_admin: Pubkey,
+    pub oracle_admin: Pubkey,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub authority: Pubkey,
+    pub old_paused_operations: u32,
+    pub new_paused_operations: u32,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DepositEvent {
     pub user: Pubkey,
@@ -54,6 +97,196 @@ pub struct LiquidateEvent {
     pub debt_repaid: u64,
     pub collateral_taken: u64,
     pub bonus: u64,
+    pub protocol_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolParamsUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub old_collateral_factor: u64,
+    pub new_collateral_factor: u64,
+    pub old_liquidation_factor: u64,
+    pub new_liquidation_factor: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolLiquidationParamsUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub old_liquidation_penalty_bps: u64,
+    pub new_liquidation_penalty_bps: u64,
+    pub old_close_factor_bps: u64,
+    pub new_close_factor_bps: u64,
+    pub old_debt_dust: u64,
+    pub new_debt_dust: u64,
+    pub old_protocol_liquidation_share: u64,
+    pub new_protocol_liquidation_share: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolAuctionConfigUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub curve: u8,
+    pub buf_bps: u64,
+    pub duration: u64,
+    pub step: u64,
+    pub cut_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolRedemptionFeeUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub old_redemption_fee_bps: u64,
+    pub new_redemption_fee_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolStatusUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub is_active: bool,
+    pub reduce_only: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BadDebtLiquidated {
+    pub liquidator: Pubkey,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub debt_cleared: u64,
+    pub stable_paid: u64,
+    pub collateral_taken: u64,
+    pub covered_by_reserve: u64,
+    pub reserve_collateral: u64,
+    pub covered_by_treasury: u64,
+    pub bad_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BadDebtHealed {
+    pub payer: Pubkey,
+    pub from_treasury: bool,
+    pub amount: u64,
+    pub remaining_bad_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionStarted {
+    pub keeper: Pubkey,
+    pub auction: Pubkey,
+    pub vault: Pubkey,
+    pub pool: Pubkey,
+    pub debt: u64,
+    pub tab: u64,
+    pub lot: u64,
+    pub start_price: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionTaken {
+    pub bidder: Pubkey,
+    pub auction: Pubkey,
+    pub collateral_bought: u64,
+    pub price: i64,
+    pub stable_paid: u64,
+    pub debt_burned: u64,
+    pub penalty_paid: u64,
+    pub remaining_tab: u64,
+    pub remaining_lot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionReset {
+    pub auction: Pubkey,
+    pub start_price: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionYanked {
+    pub auction: Pubkey,
+    pub vault: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_returned: u64,
+    pub debt_returned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub vault: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_refunded: u64,
+    pub bad_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StabilityPoolDeposited {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub new_deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StabilityPoolWithdrawn {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub new_deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StabilityPoolGainClaimed {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StabilityPoolAbsorbed {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub debt_absorbed: u64,
+    pub collateral_gained: u64,
+    pub total_deposits: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultRedeemed {
+    pub vault: Pubkey,
+    pub pool: Pubkey,
+    pub debt_redeemed: u64,
+    pub collateral_redeemed: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedeemEvent {
+    pub redeemer: Pubkey,
+    pub pool: Pubkey,
+    pub stable_redeemed: u64,
+    pub collateral_received: u64,
+    pub fee: u64,
+    pub interest_paid: u64,
     pub timestamp: i64,
 }
 
@@ -65,4 +298,280 @@ pub struct PoolInitialized {
     pub collateral_factor: u64,
     pub liquidation_factor: u64,
     pub timestamp: i64,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct PoolOracleUpdated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub feed_id: [u8; 32],
+    pub price_account: Pubkey,
+    pub max_staleness: u64,
+    pub max_confidence_bps: u64,
+    pub timestamp: i64,
+}
+#[event]
+pub struct SystemShutdown {
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCaged {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub final_price: i64,
+    pub total_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultSettled {
+    pub settler: Pubkey,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub debt_settled: u64,
+    pub collateral_taken: u64,
+    pub shortfall: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementFinalized {
+    pub settlement_supply: u64,
+    pub treasury_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementRedeemed {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub stable_burned: u64,
+    pub collateral_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolReserveWithdrawn {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PsmInitialized {
+    pub admin: Pubkey,
+    pub psm: Pubkey,
+    pub mint: Pubkey,
+    pub tin_bps: u64,
+    pub tout_bps: u64,
+    pub debt_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PsmParamsUpdated {
+    pub admin: Pubkey,
+    pub psm: Pubkey,
+    pub old_tin_bps: u64,
+    pub new_tin_bps: u64,
+    pub old_tout_bps: u64,
+    pub new_tout_bps: u64,
+    pub old_debt_ceiling: u64,
+    pub new_debt_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PsmSwapEvent {
+    pub user: Pubkey,
+    pub psm: Pubkey,
+    pub to_stable: bool,
+    pub collateral_amount: u64,
+    pub stable_amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FlashMintParamsUpdated {
+    pub admin: Pubkey,
+    pub old_fee_bps: u64,
+    pub new_fee_bps: u64,
+    pub old_cap: u64,
+    pub new_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FlashMinted {
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FlashRepaid {
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SavingsRateUpdated {
+    pub admin: Pubkey,
+    pub old_savings_rate_bps: u64,
+    pub new_savings_rate_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SavingsAccrued {
+    pub interest_paid: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SavingsDeposited {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares_minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SavingsRedeemed {
+    pub user: Pubkey,
+    pub shares_burned: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginDepositEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_amount: u64,
+    pub shares_minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginWithdrawEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_amount: u64,
+    pub shares_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginMintEvent {
+    pub user: Pubkey,
+    pub stable_amount: u64,
+    pub debt_amount: u64,
+    pub borrow_limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginRepayEvent {
+    pub user: Pubkey,
+    pub stable_amount: u64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginLiquidateEvent {
+    pub liquidator: Pubkey,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub debt_repaid: u64,
+    pub collateral_taken: u64,
+    pub protocol_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginBadDebtCleared {
+    pub caller: Pubkey,
+    pub user: Pubkey,
+    pub debt_cleared: u64,
+    pub covered_by_treasury: u64,
+    pub bad_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrossMarginSettled {
+    pub settler: Pubkey,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub debt_settled: u64,
+    pub collateral_taken: u64,
+    pub shortfall: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultOpened {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub vault: Pubkey,
+    pub vault_index: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultClosed {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub vault: Pubkey,
+    pub vault_index: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_swept: u64,
+    pub stable_swept: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolMigrated {
+    pub admin: Pubkey,
+    pub pool: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultMigrated {
+    pub migrator: Pubkey,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub legacy_vault: Pubkey,
+    pub vault: Pubkey,
+    pub vault_index: u16,
+    pub debt: u64,
+    pub timestamp: i64,
+}
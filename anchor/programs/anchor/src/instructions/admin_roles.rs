@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+
+/// First step of an admin transfer: record the proposed admin (admin only).
+/// Proposing `Pubkey::default()` cancels a pending transfer.
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        admin: ctx.accounts.admin.key(),
+        pending_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Second step of an admin transfer: the proposed admin signs to take over.
+/// The pool registry authority follows the global admin.
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    let new_admin = ctx.accounts.pending_admin.key();
+    let old_admin = global_state.admin;
+
+    global_state.admin = new_admin;
+    global_state.pending_admin = Pubkey::default();
+    ctx.accounts.pool_registry.authority = new_admin;
+
+    emit!(AdminTransferred {
+        old_admin,
+        new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Assign the operational roles (admin only)
+pub fn set_roles(
+    ctx: Context<SetRoles>,
+    risk_admin: Pubkey,
+    oracle_admin: Pubkey,
+    guardian: Pubkey,
+) -> Result<()> {
+    require!(risk_admin != Pubkey::default(), StableError::InvalidParameter);
+    require!(oracle_admin != Pubkey::default(), StableError::InvalidParameter);
+    require!(guardian != Pubkey::default(), StableError::InvalidParameter);
+
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.risk_admin = risk_admin;
+    global_state.oracle_admin = oracle_admin;
+    global_state.guardian = guardian;
+
+    emit!(RolesUpdated {
+        admin: ctx.accounts.admin.key(),
+        risk_admin,
+        oracle_admin,
+        guardian,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+        constraint = global_state.admin == admin.key() @ StableError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+        constraint = global_state.pending_admin != Pubkey::default() @ StableError::Unauthorized,
+        constraint = global_state.pending_admin == pending_admin.key() @ StableError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+        constraint = global_state.admin == admin.key() @ StableError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
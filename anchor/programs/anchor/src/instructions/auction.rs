@@ -0,0 +1,578 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Move an unhealthy vault into a Dutch auction (anyone can call).
+/// All of the vault's debt and collateral leave the vault: the debt plus the pool's
+/// liquidation penalty becomes the auction tab, the collateral becomes the lot.
+pub fn start_auction(ctx: Context<StartAuction>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(ctx.accounts.pool.auction.duration > 0, StableError::AuctionNotConfigured);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+
+    let pool = &mut ctx.accounts.pool;
+    let user_vault = &mut ctx.accounts.user_vault;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let total_debt = sync_vault_debt(user_vault, pool)?;
+    require!(total_debt > 0, StableError::InvalidParameter);
+
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, pool)?.price;
+
+    let shares = user_vault.collateral_shares;
+    let lot = if shares == 0 {
+        0
+    } else {
+        calculate_amount_from_shares(shares, pool.total_collateral, pool.total_shares)?
+    };
+    require!(lot > 0, StableError::InsufficientCollateral);
+
+    // same health check as liquidate_vault
+    let collateral_value = calculate_collateral_value(lot, oracle_price, ctx.accounts.collateral_mint.decimals)?;
+    let collateral_ratio = calculate_collateral_ratio_bps(collateral_value, total_debt)?;
+    require!(
+        collateral_ratio < pool.liquidation_factor,
+        StableError::CannotLiquidateHealthyVault
+    );
+
+    let tab = ((total_debt as u128)
+        .checked_mul((BASIS_POINTS_DIVISOR + pool.liquidation_penalty_bps) as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+    let start_price = auction_start_price(&pool.auction, oracle_price)?;
+
+    // take the debt off the vault and park it with the auction
+    repay_vault_debt(user_vault, pool, global_state, total_debt)?;
+    global_state.auction_debt = global_state.auction_debt
+        .checked_add(total_debt)
+        .ok_or(StableError::Overflow)?;
+
+    user_vault.collateral_shares = 0;
+    user_vault.last_update = current_time;
+
+    pool.total_collateral = pool.total_collateral
+        .checked_sub(lot)
+        .ok_or(StableError::Overflow)?;
+    pool.total_shares = pool.total_shares
+        .checked_sub(shares)
+        .ok_or(StableError::Overflow)?;
+    pool.auction_collateral = pool.auction_collateral
+        .checked_add(lot)
+        .ok_or(StableError::Overflow)?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.vault = user_vault.key();
+    auction.pool = pool.key();
+    auction.keeper = ctx.accounts.keeper.key();
+    auction.debt = total_debt;
+    auction.tab = tab;
+    auction.lot = lot;
+    auction.start_price = start_price;
+    auction.start_time = current_time;
+    auction.bump = ctx.bumps.auction;
+
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
+
+    emit!(AuctionStarted {
+        keeper: auction.keeper,
+        auction: auction.key(),
+        vault: auction.vault,
+        pool: auction.pool,
+        debt: total_debt,
+        tab,
+        lot,
+        start_price,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Buy up to `max_collateral` from an auction at its current price, paying in WUSD.
+/// Fails if the price is above `max_price` (8 decimals).
+/// Payments burn the auction's debt first, the penalty part goes to the treasury.
+/// Once the tab is raised or the lot is sold out the auction settles and closes.
+/// After shutdown auctions can only be yanked.
+pub fn take_auction(ctx: Context<TakeAuction>, max_collateral: u64, max_price: i64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(max_collateral > 0, StableError::InvalidParameter);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+    let pool = &mut ctx.accounts.pool;
+    let global_state = &mut ctx.accounts.global_state;
+    let collateral_decimals = ctx.accounts.collateral_mint.decimals;
+
+    let price = auction_price(&pool.auction, auction.start_price, current_time - auction.start_time)?;
+    require!(price > 0, StableError::AuctionNeedsReset);
+    require!(price <= max_price, StableError::AuctionPriceTooHigh);
+
+    // never sell more collateral than is needed to raise the tab
+    let mut slice = max_collateral.min(auction.lot);
+    let mut owe = calculate_collateral_value(slice, price, collateral_decimals)?;
+    if owe >= auction.tab {
+        owe = auction.tab;
+        slice = calculate_collateral_amount(owe, price, collateral_decimals)?
+            .max(1)
+            .min(auction.lot);
+    }
+    require!(slice > 0 && owe > 0, StableError::InvalidParameter);
+
+    let burn_amount = owe.min(auction.debt);
+    let penalty_amount = owe
+        .checked_sub(burn_amount)
+        .ok_or(StableError::Overflow)?;
+
+    auction.debt = auction.debt
+        .checked_sub(burn_amount)
+        .ok_or(StableError::Overflow)?;
+    auction.tab = auction.tab
+        .checked_sub(owe)
+        .ok_or(StableError::Overflow)?;
+    auction.lot = auction.lot
+        .checked_sub(slice)
+        .ok_or(StableError::Overflow)?;
+
+    pool.auction_collateral = pool.auction_collateral
+        .checked_sub(slice)
+        .ok_or(StableError::Overflow)?;
+    global_state.auction_debt = global_state.auction_debt.saturating_sub(burn_amount);
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(penalty_amount)
+        .ok_or(StableError::Overflow)?;
+
+    emit!(AuctionTaken {
+        bidder: ctx.accounts.bidder.key(),
+        auction: auction.key(),
+        collateral_bought: slice,
+        price,
+        stable_paid: owe,
+        debt_burned: burn_amount,
+        penalty_paid: penalty_amount,
+        remaining_tab: auction.tab,
+        remaining_lot: auction.lot,
+        timestamp: current_time,
+    });
+
+    let is_finished = auction.tab == 0 || auction.lot == 0;
+    if is_finished {
+        settle_auction(auction, pool, global_state, &mut ctx.accounts.user_vault, current_time)?;
+    }
+
+    if burn_amount > 0 {
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.bidder_stable_account.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            burn_amount,
+        )?;
+    }
+
+    if penalty_amount > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.bidder_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            penalty_amount,
+        )?;
+    }
+
+    let seeds = &[SEED_POOL, ctx.accounts.pool.mint.as_ref(), &[ctx.accounts.pool.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_collateral_account.to_account_info(),
+                to: ctx.accounts.bidder_collateral_account.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        slice,
+    )?;
+
+    if is_finished {
+        ctx.accounts.auction.close(ctx.accounts.keeper.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+/// Restart an auction whose price curve has run out, from a fresh oracle price (anyone can call)
+pub fn reset_auction(ctx: Context<ResetAuction>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let pool = &ctx.accounts.pool;
+    let auction = &mut ctx.accounts.auction;
+
+    let price = auction_price(&pool.auction, auction.start_price, current_time - auction.start_time)?;
+    require!(price == 0, StableError::AuctionStillRunning);
+
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, pool)?.price;
+    auction.start_price = auction_start_price(&pool.auction, oracle_price)?;
+    auction.start_time = current_time;
+
+    emit!(AuctionReset {
+        auction: auction.key(),
+        start_price: auction.start_price,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Cancel an auction after shutdown (anyone can call).
+/// The unsold lot and the unpaid debt go back to the vault so `settle_vault` can net them
+/// at the final price; the unpaid penalty is dropped.
+pub fn yank_auction(ctx: Context<YankAuction>) -> Result<()> {
+    require!(ctx.accounts.global_state.is_shutdown, StableError::SystemNotShutdown);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+
+    let auction = &mut ctx.accounts.auction;
+    let pool = &mut ctx.accounts.pool;
+    let global_state = &mut ctx.accounts.global_state;
+    let user_vault = &mut ctx.accounts.user_vault;
+
+    let collateral_returned = auction.lot;
+    if collateral_returned > 0 {
+        let shares = calculate_shares_from_amount(
+            collateral_returned,
+            pool.total_shares,
+            pool.total_collateral,
+        )?;
+
+        pool.auction_collateral = pool.auction_collateral
+            .checked_sub(collateral_returned)
+            .ok_or(StableError::Overflow)?;
+        pool.total_collateral = pool.total_collateral
+            .checked_add(collateral_returned)
+            .ok_or(StableError::Overflow)?;
+        pool.total_shares = pool.total_shares
+            .checked_add(shares)
+            .ok_or(StableError::Overflow)?;
+
+        user_vault.collateral_shares = user_vault.collateral_shares
+            .checked_add(shares)
+            .ok_or(StableError::Overflow)?;
+    }
+
+    let debt_returned = auction.debt;
+    if debt_returned > 0 {
+        global_state.auction_debt = global_state.auction_debt.saturating_sub(debt_returned);
+        add_vault_debt(user_vault, pool, global_state, debt_returned)?;
+    }
+    user_vault.last_update = current_time;
+
+    auction.lot = 0;
+    auction.debt = 0;
+
+    emit!(AuctionYanked {
+        auction: auction.key(),
+        vault: auction.vault,
+        pool: auction.pool,
+        collateral_returned,
+        debt_returned,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Wrap up a finished auction: unsold collateral goes back to the vault as shares,
+/// debt left after the lot sold out is recorded as bad debt
+fn settle_auction(
+    auction: &mut Auction,
+    pool: &mut CollateralPool,
+    global_state: &mut GlobalState,
+    user_vault: &mut UserVault,
+    current_time: i64,
+) -> Result<()> {
+    let collateral_refunded = auction.lot;
+    if collateral_refunded > 0 {
+        let shares = calculate_shares_from_amount(
+            collateral_refunded,
+            pool.total_shares,
+            pool.total_collateral,
+        )?;
+
+        pool.auction_collateral = pool.auction_collateral
+            .checked_sub(collateral_refunded)
+            .ok_or(StableError::Overflow)?;
+        pool.total_collateral = pool.total_collateral
+            .checked_add(collateral_refunded)
+            .ok_or(StableError::Overflow)?;
+        pool.total_shares = pool.total_shares
+            .checked_add(shares)
+            .ok_or(StableError::Overflow)?;
+
+        user_vault.collateral_shares = user_vault.collateral_shares
+            .checked_add(shares)
+            .ok_or(StableError::Overflow)?;
+        user_vault.last_update = current_time;
+    }
+
+    let unpaid_debt = auction.debt;
+    if unpaid_debt > 0 {
+        global_state.auction_debt = global_state.auction_debt.saturating_sub(unpaid_debt);
+        global_state.bad_debt = global_state.bad_debt
+            .checked_add(unpaid_debt)
+            .ok_or(StableError::Overflow)?;
+    }
+
+    auction.lot = 0;
+    auction.debt = 0;
+
+    emit!(AuctionSettled {
+        vault: auction.vault,
+        pool: auction.pool,
+        collateral_refunded,
+        bad_debt: unpaid_debt,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+        constraint = pool_registry.pools.contains(&pool.key()) @ StableError::InvalidPool
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_VAULT, user_vault.owner.as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    #[account(
+        init,
+        payer = keeper,
+        space = 8 + Auction::INIT_SPACE,
+        seeds = [SEED_AUCTION, user_vault.key().as_ref()],
+        bump
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TakeAuction<'info> {
+    pub bidder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, auction.vault.as_ref()],
+        bump = auction.bump,
+        constraint = auction.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(
+        mut,
+        constraint = user_vault.key() == auction.vault @ StableError::VaultNotFound
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: receives the auction rent when it closes, checked against auction.keeper
+    #[account(
+        mut,
+        constraint = keeper.key() == auction.keeper @ StableError::Unauthorized
+    )]
+    pub keeper: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = bidder_stable_account.owner == bidder.key(),
+        constraint = bidder_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub bidder_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = bidder_collateral_account.owner == bidder.key(),
+        constraint = bidder_collateral_account.mint == collateral_mint.key(),
+    )]
+    pub bidder_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ResetAuction<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, auction.vault.as_ref()],
+        bump = auction.bump,
+        constraint = auction.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct YankAuction<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        close = keeper,
+        seeds = [SEED_AUCTION, auction.vault.as_ref()],
+        bump = auction.bump,
+        constraint = auction.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(
+        mut,
+        constraint = user_vault.key() == auction.vault @ StableError::VaultNotFound
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: receives the auction rent, checked against auction.keeper
+    #[account(
+        mut,
+        constraint = keeper.key() == auction.keeper @ StableError::Unauthorized
+    )]
+    pub keeper: AccountInfo<'info>,
+}
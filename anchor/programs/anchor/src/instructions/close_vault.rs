@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+
+/// Close an empty vault and return its rent to the owner.
+/// The vault must hold no shares, debt or interest and must not have a running auction.
+pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+    let user_vault = &ctx.accounts.user_vault;
+
+    require!(
+        user_vault.collateral_shares == 0
+            && user_vault.normalized_debt == 0
+            && user_vault.debt_amount == 0
+            && user_vault.accrued_interest == 0,
+        StableError::VaultNotEmpty
+    );
+    // a running auction refunds its leftover collateral into the vault
+    require!(ctx.accounts.auction.data_is_empty(), StableError::AuctionStillRunning);
+
+    emit!(VaultClosed {
+        user: user_vault.owner,
+        pool: user_vault.pool,
+        vault: user_vault.key(),
+        vault_index: user_vault.vault_index,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [SEED_VAULT, user.key().as_ref(), user_vault.pool.as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Account<'info, UserVault>,
+
+    /// CHECK: auction PDA of the vault, only checked to be empty
+    #[account(
+        seeds = [SEED_AUCTION, user_vault.key().as_ref()],
+        bump
+    )]
+    pub auction: AccountInfo<'info>,
+}
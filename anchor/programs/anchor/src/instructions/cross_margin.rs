@@ -0,0 +1,951 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Open a cross-margin account for the signer
+pub fn open_cross_margin_account(ctx: Context<OpenCrossMarginAccount>) -> Result<()> {
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+
+    let account = &mut ctx.accounts.cross_margin_account;
+    account.owner = ctx.accounts.user.key();
+    account.positions = Vec::new();
+    account.debt_amount = 0;
+    account.accrued_interest = 0;
+    account.normalized_debt = 0;
+    account.last_update = Clock::get()?.unix_timestamp;
+    account.bump = ctx.bumps.cross_margin_account;
+
+    Ok(())
+}
+
+/// Deposit collateral into the account's position in `pool`.
+/// The collateral joins the pool's shares like a vault deposit.
+pub fn cross_margin_deposit(ctx: Context<CrossMarginDeposit>, collateral_amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_DEPOSIT),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(collateral_amount > 0, StableError::InvalidParameter);
+
+    let pool = &mut ctx.accounts.pool;
+    let account = &mut ctx.accounts.cross_margin_account;
+
+    require!(pool.is_active, StableError::PoolNotActive);
+    require!(!pool.reduce_only, StableError::PoolReduceOnly);
+
+    let shares = calculate_shares_from_amount(collateral_amount, pool.total_shares, pool.total_collateral)?;
+
+    pool.total_collateral = pool.total_collateral
+        .checked_add(collateral_amount)
+        .ok_or(StableError::Overflow)?;
+    pool.total_shares = pool.total_shares
+        .checked_add(shares)
+        .ok_or(StableError::Overflow)?;
+    account.add_shares(pool.key(), ctx.accounts.collateral_mint.decimals, shares)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    account.last_update = current_time;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_collateral_account.to_account_info(),
+                to: ctx.accounts.pool_collateral_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        collateral_amount,
+    )?;
+
+    emit!(CrossMarginDepositEvent {
+        user: ctx.accounts.user.key(),
+        pool: pool.key(),
+        collateral_amount,
+        shares_minted: shares,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Withdraw collateral shares from the account's position in `pool`.
+/// With debt outstanding, `remaining_accounts` must hold [pool, price_feed] for every
+/// position (in position order, as they are after the withdrawal) and the account
+/// has to stay within its borrow limit.
+pub fn cross_margin_withdraw<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CrossMarginWithdraw<'info>>,
+    shares_to_burn: u64,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_WITHDRAW),
+        StableError::OperationPaused
+    );
+    require!(shares_to_burn > 0, StableError::InvalidParameter);
+
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+
+    require!(pool.total_shares > 0, StableError::InvalidParameter);
+    let collateral_to_withdraw = calculate_amount_from_shares(shares_to_burn, pool.total_collateral, pool.total_shares)?;
+    require!(collateral_to_withdraw > 0, StableError::InvalidParameter);
+
+    account.remove_shares(&pool_key, shares_to_burn)?;
+    pool.total_collateral = pool.total_collateral
+        .checked_sub(collateral_to_withdraw)
+        .ok_or(StableError::Overflow)?;
+    pool.total_shares = pool.total_shares
+        .checked_sub(shares_to_burn)
+        .ok_or(StableError::Overflow)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if account.normalized_debt > 0 {
+        // after shutdown the debt has to be repaid or netted by settle_cross_margin first
+        require!(!global_state.is_shutdown, StableError::VaultNotSettled);
+
+        accrue_cross_margin_interest(global_state, current_time)?;
+        let total_debt = sync_cross_margin_debt(account, global_state)?;
+
+        let updated_pool: &CollateralPool = pool;
+        let health = cross_margin_health(account, ctx.remaining_accounts, Some((pool_key, updated_pool)))?;
+        require!(total_debt <= health.borrow_limit, StableError::CollateralRatioTooLow);
+    }
+
+    account.last_update = current_time;
+
+    let seeds = &[SEED_POOL, ctx.accounts.pool.mint.as_ref(), &[ctx.accounts.pool.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_collateral_account.to_account_info(),
+                to: ctx.accounts.user_collateral_account.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        collateral_to_withdraw,
+    )?;
+
+    emit!(CrossMarginWithdrawEvent {
+        user: ctx.accounts.user.key(),
+        pool: pool_key,
+        collateral_amount: collateral_to_withdraw,
+        shares_burned: shares_to_burn,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Mint WUSD against all of the account's positions.
+/// `remaining_accounts` must hold [pool, price_feed] for every position, in position order.
+pub fn cross_margin_mint<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CrossMarginMint<'info>>,
+    stable_amount: u64,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_MINT),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(stable_amount > 0, StableError::InvalidParameter);
+
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_cross_margin_interest(global_state, current_time)?;
+
+    let new_total_debt = global_state.total_debt
+        .checked_add(stable_amount)
+        .ok_or(StableError::Overflow)?;
+    require!(
+        new_total_debt <= global_state.debt_ceiling,
+        StableError::DebtCeilingReached
+    );
+
+    let new_account_debt = sync_cross_margin_debt(account, global_state)?
+        .checked_add(stable_amount)
+        .ok_or(StableError::Overflow)?;
+    let health = cross_margin_health(account, ctx.remaining_accounts, None)?;
+    require!(
+        new_account_debt <= health.borrow_limit,
+        StableError::CollateralRatioTooLowForMint
+    );
+    // same minimum debt as a vault in any of the account's pools
+    require!(new_account_debt >= health.min_debt, StableError::DebtBelowDust);
+
+    add_cross_margin_debt(account, global_state, stable_amount)?;
+    account.last_update = current_time;
+
+    let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.user_stable_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        stable_amount,
+    )?;
+
+    emit!(CrossMarginMintEvent {
+        user: ctx.accounts.user.key(),
+        stable_amount,
+        debt_amount: ctx.accounts.cross_margin_account.debt_amount,
+        borrow_limit: health.borrow_limit,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Repay cross-margin debt. Interest goes to the treasury, the rest is burned.
+/// Debt left behind must be zero or at least the account's minimum debt.
+/// Stays open after shutdown so accounts can be unwound.
+/// `remaining_accounts` must hold the pool of every position, in position order.
+pub fn cross_margin_repay<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CrossMarginRepay<'info>>,
+    repay_amount: u64,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_REPAY),
+        StableError::OperationPaused
+    );
+    require!(repay_amount > 0, StableError::InvalidParameter);
+
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_cross_margin_interest(global_state, current_time)?;
+
+    let total_owed = sync_cross_margin_debt(account, global_state)?;
+    require!(repay_amount <= total_owed, StableError::RepayAmountExceedsDebt);
+    let debt_left = total_owed - repay_amount;
+    require!(
+        debt_left == 0 || debt_left >= cross_margin_min_debt(account, ctx.remaining_accounts)?,
+        StableError::DebtBelowDust
+    );
+
+    let (principal_paid, interest_paid) = repay_cross_margin_debt(account, global_state, repay_amount)?;
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(interest_paid)
+        .ok_or(StableError::Overflow)?;
+    account.last_update = current_time;
+
+    if interest_paid > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            interest_paid,
+        )?;
+    }
+
+    let burn_amount = repay_amount
+        .checked_sub(interest_paid)
+        .ok_or(StableError::Overflow)?;
+    if burn_amount > 0 {
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.user_stable_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            burn_amount,
+        )?;
+    }
+
+    emit!(CrossMarginRepayEvent {
+        user: ctx.accounts.user.key(),
+        stable_amount: repay_amount,
+        principal_paid,
+        interest_paid,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Liquidate an account whose debt is above its liquidation limit, seizing collateral from `pool`.
+/// Repayment is capped by that pool's close factor (with its dust rule) and the collateral plus the
+/// pool's liquidation penalty is seized, sized at price + conf. As for isolated vaults, the pool
+/// reserve takes its share of the penalty and the pool's stability pool absorbs the debt first.
+/// `remaining_accounts` must hold [pool, price_feed] for every position, in position order.
+pub fn cross_margin_liquidate<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CrossMarginLiquidate<'info>>,
+    debt_to_repay: u64,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(debt_to_repay > 0, StableError::InvalidParameter);
+
+    let pool_key = ctx.accounts.pool.key();
+    let user_key = ctx.accounts.cross_margin_account.owner;
+    let pool = &mut ctx.accounts.pool;
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_cross_margin_interest(global_state, current_time)?;
+
+    let total_debt = sync_cross_margin_debt(account, global_state)?;
+    let health = cross_margin_health(account, ctx.remaining_accounts, None)?;
+    require!(
+        total_debt > health.liquidation_limit,
+        StableError::CannotLiquidateHealthyVault
+    );
+
+    let position = *account.position(&pool_key).ok_or(StableError::InvalidPool)?;
+    let position_collateral = calculate_amount_from_shares(
+        position.collateral_shares,
+        pool.total_collateral,
+        pool.total_shares,
+    )?;
+
+    // limit liquidation amount to the pool's close factor
+    let close_factor_amount = ((total_debt as u128)
+        .checked_mul(pool.close_factor_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+    let max_liquidation = if total_debt - close_factor_amount < pool.debt_dust {
+        total_debt
+    } else {
+        close_factor_amount
+    };
+    let mut actual_debt_to_repay = debt_to_repay.min(max_liquidation);
+
+    let payout_price = load_collateral_price(&ctx.accounts.price_feed, pool)?.max_price()?;
+    let penalty_multiplier = (BASIS_POINTS_DIVISOR + pool.liquidation_penalty_bps) as u128;
+    let value_to_take = ((actual_debt_to_repay as u128)
+        .checked_mul(penalty_multiplier)
+        .ok_or(StableError::Overflow)?
+        / BASIS_POINTS_DIVISOR as u128) as u64;
+    let mut collateral_to_take = calculate_collateral_amount(value_to_take, payout_price, position.decimals)?;
+
+    // a position too small for the full repayment is taken whole and the repayment scaled down
+    if collateral_to_take >= position_collateral {
+        collateral_to_take = position_collateral;
+        let position_value = calculate_collateral_value(position_collateral, payout_price, position.decimals)?;
+        actual_debt_to_repay = actual_debt_to_repay.min(
+            ((position_value as u128)
+                .checked_mul(BASIS_POINTS_DIVISOR as u128)
+                .ok_or(StableError::Overflow)?
+                / penalty_multiplier) as u64,
+        );
+    }
+    require!(actual_debt_to_repay > 0 && collateral_to_take > 0, StableError::InvalidParameter);
+
+    // the bonus part of the seized collateral is split with the pool reserve
+    let debt_collateral = calculate_collateral_amount(actual_debt_to_repay, payout_price, position.decimals)?;
+    let bonus_collateral = collateral_to_take.saturating_sub(debt_collateral);
+    let protocol_fee = ((bonus_collateral as u128)
+        .checked_mul(pool.protocol_liquidation_share as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+    let mut liquidator_collateral = collateral_to_take
+        .checked_sub(protocol_fee)
+        .ok_or(StableError::Overflow)?;
+
+    // the pool's stability pool absorbs as much of the debt as it can,
+    // the external liquidator covers the rest
+    let absorbed = absorb_liquidation(
+        load_stability_pool(&ctx.accounts.stability_pool)?,
+        actual_debt_to_repay,
+        liquidator_collateral,
+    )?;
+    let (stability_pool_debt, stability_pool_collateral) = absorbed
+        .as_ref()
+        .map_or((0, 0), |(_, debt, collateral)| (*debt, *collateral));
+    let liquidator_debt = actual_debt_to_repay
+        .checked_sub(stability_pool_debt)
+        .ok_or(StableError::Overflow)?;
+    liquidator_collateral = liquidator_collateral
+        .checked_sub(stability_pool_collateral)
+        .ok_or(StableError::Overflow)?;
+
+    let shares_to_take = if collateral_to_take == position_collateral {
+        position.collateral_shares
+    } else {
+        ((collateral_to_take as u128)
+            .checked_mul(pool.total_shares as u128)
+            .ok_or(StableError::Overflow)?
+            / pool.total_collateral as u128) as u64
+    };
+
+    account.remove_shares(&pool_key, shares_to_take)?;
+    pool.total_collateral = pool.total_collateral
+        .checked_sub(collateral_to_take)
+        .ok_or(StableError::Overflow)?;
+    pool.total_shares = pool.total_shares
+        .checked_sub(shares_to_take)
+        .ok_or(StableError::Overflow)?;
+
+    let (_, interest_paid) = repay_cross_margin_debt(account, global_state, actual_debt_to_repay)?;
+    // only the liquidator's WUSD is routed to the treasury, stability pool funds are burned
+    let treasury_amount = interest_paid.min(liquidator_debt);
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(treasury_amount)
+        .ok_or(StableError::Overflow)?;
+    account.last_update = current_time;
+
+    let seeds = &[SEED_POOL, ctx.accounts.pool.mint.as_ref(), &[ctx.accounts.pool.bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    if liquidator_collateral > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            liquidator_collateral,
+        )?;
+    }
+
+    if protocol_fee > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.pool_reserve.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            protocol_fee,
+        )?;
+    }
+
+    if let Some((stability_pool, _, _)) = absorbed {
+        stability_pool.try_serialize(&mut &mut ctx.accounts.stability_pool.try_borrow_mut_data()?[..])?;
+
+        emit!(StabilityPoolAbsorbed {
+            pool: pool_key,
+            user: user_key,
+            debt_absorbed: stability_pool_debt,
+            collateral_gained: stability_pool_collateral,
+            total_deposits: stability_pool.total_deposits,
+            timestamp: current_time,
+        });
+
+        absorb_stability_pool_debt(&ctx, stability_pool_debt, stability_pool_collateral, signer_seeds)?;
+    }
+
+    if treasury_amount > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.liquidator_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            treasury_amount,
+        )?;
+    }
+
+    let burn_amount = liquidator_debt
+        .checked_sub(treasury_amount)
+        .ok_or(StableError::Overflow)?;
+    if burn_amount > 0 {
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stable_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            burn_amount,
+        )?;
+    }
+
+    emit!(CrossMarginLiquidateEvent {
+        liquidator: ctx.accounts.liquidator.key(),
+        user: user_key,
+        pool: pool_key,
+        debt_repaid: actual_debt_to_repay,
+        collateral_taken: collateral_to_take,
+        protocol_fee,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Burn `debt` from the stability pool's WUSD and move `collateral` from the pool into it
+fn absorb_stability_pool_debt(
+    ctx: &Context<CrossMarginLiquidate>,
+    debt: u64,
+    collateral: u64,
+    pool_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let (Some(stability_pool_stable_account), Some(stability_pool_collateral_account)) = (
+        ctx.accounts.stability_pool_stable_account.as_ref(),
+        ctx.accounts.stability_pool_collateral_account.as_ref(),
+    ) else {
+        return err!(StableError::StabilityPoolAccountsMissing);
+    };
+
+    let pool_key = ctx.accounts.pool.key();
+    let stability_pool_seeds = &[SEED_STABILITY_POOL, pool_key.as_ref(), &[ctx.bumps.stability_pool]];
+
+    token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: stability_pool_stable_account.to_account_info(),
+                authority: ctx.accounts.stability_pool.to_account_info(),
+            },
+            &[&stability_pool_seeds[..]],
+        ),
+        debt,
+    )?;
+
+    if collateral > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: stability_pool_collateral_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                pool_signer_seeds,
+            ),
+            collateral,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Clear the debt of an account whose positions have all been liquidated (anyone can call).
+/// The debt is covered by burning treasury WUSD and whatever the treasury cannot cover
+/// is recorded as protocol bad debt.
+pub fn cross_margin_clear_bad_debt(ctx: Context<CrossMarginClearBadDebt>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+    require!(account.positions.is_empty(), StableError::VaultNotEmpty);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_cross_margin_interest(global_state, current_time)?;
+
+    let total_debt = sync_cross_margin_debt(account, global_state)?;
+    require!(total_debt > 0, StableError::InvalidParameter);
+
+    let covered_by_treasury = total_debt.min(ctx.accounts.treasury.amount);
+    let new_bad_debt = total_debt
+        .checked_sub(covered_by_treasury)
+        .ok_or(StableError::Overflow)?;
+
+    repay_cross_margin_debt(account, global_state, total_debt)?;
+    global_state.bad_debt = global_state.bad_debt
+        .checked_add(new_bad_debt)
+        .ok_or(StableError::Overflow)?;
+    account.last_update = current_time;
+
+    if covered_by_treasury > 0 {
+        let treasury_seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&treasury_seeds[..]],
+            ),
+            covered_by_treasury,
+        )?;
+    }
+
+    emit!(CrossMarginBadDebtCleared {
+        caller: ctx.accounts.caller.key(),
+        user: ctx.accounts.cross_margin_account.owner,
+        debt_cleared: total_debt,
+        covered_by_treasury,
+        bad_debt: new_bad_debt,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OpenCrossMarginAccount<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CrossMarginAccount::INIT_SPACE,
+        seeds = [SEED_CROSS_MARGIN, user.key().as_ref()],
+        bump
+    )]
+    pub cross_margin_account: Account<'info, CrossMarginAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginDeposit<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+        constraint = pool_registry.pools.contains(&pool.key()) @ StableError::InvalidPool
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, user.key().as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == collateral_mint.key(),
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginWithdraw<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, user.key().as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == pool.mint,
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = pool.mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginMint<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, user.key().as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA for minting stablecoins
+    #[account(
+        seeds = [b"mint_authority"],
+        bump
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginRepay<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, user.key().as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginLiquidate<'info> {
+    pub liquidator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, cross_margin_account.owner.as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = liquidator_stable_account.owner == liquidator.key(),
+        constraint = liquidator_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub liquidator_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = liquidator_collateral_account.owner == liquidator.key(),
+        constraint = liquidator_collateral_account.mint == pool.mint,
+    )]
+    pub liquidator_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = pool.mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_reserve: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: the pool's stability pool, may not be initialized; read and written by cross_margin_liquidate.
+    /// When it can absorb debt it does so before the liquidator and its token accounts are required.
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_stable_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_collateral_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrossMarginClearBadDebt<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, cross_margin_account.owner.as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -3,21 +3,49 @@ use crate::constants::*;
 use crate::errors::*;
 use crate::states::*;
 use crate::events::*;
+use crate::utils::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-pub fn deposit(ctx: Context<Deposit>, collateral_amount: u64) -> Result<()> {
+/// Deposit collateral into the user's vault number `vault_index` in this pool.
+/// A new vault has to take the next index from the user's vault counter.
+pub fn deposit(ctx: Context<Deposit>, vault_index: u16, collateral_amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_DEPOSIT),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
     require!(collateral_amount > 0, StableError::InvalidParameter);
 
     let pool = &mut ctx.accounts.pool;
     let user_vault = &mut ctx.accounts.user_vault;
 
     require!(pool.is_active, StableError::PoolNotActive);
+    require!(!pool.reduce_only, StableError::PoolReduceOnly);
 
     // Initialize vault fields if this is a new vault (owner is zeroed)
     if user_vault.owner == Pubkey::default() {
+        let vault_counter = &mut ctx.accounts.vault_counter;
+        if vault_counter.owner == Pubkey::default() {
+            vault_counter.owner = ctx.accounts.user.key();
+            vault_counter.bump = ctx.bumps.vault_counter;
+        }
+        require!(vault_index == vault_counter.next_index, StableError::InvalidVaultIndex);
+        vault_counter.next_index = vault_counter.next_index
+            .checked_add(1)
+            .ok_or(StableError::Overflow)?;
+
         user_vault.owner = ctx.accounts.user.key();
         user_vault.pool = pool.key();
         user_vault.bump = ctx.bumps.user_vault;
+        user_vault.vault_index = vault_index;
+
+        emit!(VaultOpened {
+            user: user_vault.owner,
+            pool: user_vault.pool,
+            vault: user_vault.key(),
+            vault_index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
     }
 
     let transfer_ctx = CpiContext::new(
@@ -57,6 +85,8 @@ pub fn deposit(ctx: Context<Deposit>, collateral_amount: u64) -> Result<()> {
 
     user_vault.last_update = Clock::get()?.unix_timestamp;
 
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
+
     emit!(DepositEvent {
         user: ctx.accounts.user.key(),
         pool: pool.key(),
@@ -69,6 +99,7 @@ pub fn deposit(ctx: Context<Deposit>, collateral_amount: u64) -> Result<()> {
 }
 
 #[derive(Accounts)]
+#[instruction(vault_index: u16)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -112,11 +143,27 @@ pub struct Deposit<'info> {
         init_if_needed,
         payer = user,
         space = 8 + UserVault::INIT_SPACE,
-        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref()],
+        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref(), vault_index.to_le_bytes().as_ref()],
         bump
     )]
     pub user_vault: Account<'info, UserVault>,
 
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVaultCounter::INIT_SPACE,
+        seeds = [SEED_VAULT_COUNTER, user.key().as_ref()],
+        bump
+    )]
+    pub vault_counter: Account<'info, UserVaultCounter>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 }
\ No newline at end of file
@@ -0,0 +1,367 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked, ID as INSTRUCTIONS_SYSVAR_ID,
+};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+
+/// Update the flash mint fee and per-call cap (risk admin only). A cap of 0 disables flash mints.
+pub fn update_flash_mint_params(
+    ctx: Context<UpdateFlashMintParams>,
+    fee_bps: u64,
+    cap: u64,
+) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    require!(fee_bps <= MAX_FLASH_MINT_FEE_BPS, StableError::InvalidParameter);
+
+    let global_state = &mut ctx.accounts.global_state;
+
+    let old_fee_bps = global_state.flash_mint_fee_bps;
+    let old_cap = global_state.flash_mint_cap;
+
+    global_state.flash_mint_fee_bps = fee_bps;
+    global_state.flash_mint_cap = cap;
+
+    emit!(FlashMintParamsUpdated {
+        admin: ctx.accounts.authority.key(),
+        old_fee_bps,
+        new_fee_bps: fee_bps,
+        old_cap,
+        new_cap: cap,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Mint `amount` WUSD without collateral.
+/// A `flash_repay` with the same amount must come later in the same transaction,
+/// checked through the instructions sysvar. Has to be a top-level instruction.
+pub fn flash_mint(ctx: Context<FlashMint>, amount: u64) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    require!(
+        !global_state.is_paused(PAUSE_FLASH_MINT),
+        StableError::OperationPaused
+    );
+    require!(!global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+    require!(amount <= global_state.flash_mint_cap, StableError::FlashMintCapExceeded);
+    require!(global_state.flash_minted == 0, StableError::FlashMintOutstanding);
+
+    // under CPI the sysvar describes the outer instruction, not this one
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        StableError::FlashMintCpiNotAllowed
+    );
+    require_flash_repay(&ctx.accounts.instructions, amount)?;
+
+    global_state.flash_minted = amount;
+
+    let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.borrower_stable_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    emit!(FlashMinted {
+        borrower: ctx.accounts.borrower.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Close the outstanding flash mint: burns `amount` WUSD and sends the flash mint fee to the treasury.
+pub fn flash_repay(ctx: Context<FlashRepay>, amount: u64) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    require!(
+        amount > 0 && global_state.flash_minted == amount,
+        StableError::FlashRepayMismatch
+    );
+
+    let fee = ((amount as u128)
+        .checked_mul(global_state.flash_mint_fee_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+
+    global_state.flash_minted = 0;
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(fee)
+        .ok_or(StableError::Overflow)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.borrower_stable_account.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    if fee > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    emit!(FlashRepaid {
+        borrower: ctx.accounts.borrower.key(),
+        amount,
+        fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Look for a `flash_repay(amount)` of this program after the current instruction
+fn require_flash_repay(instructions: &AccountInfo, amount: u64) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)? as usize;
+
+    let mut index = current_index + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID && ix.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR) {
+            let disc_len = crate::instruction::FlashRepay::DISCRIMINATOR.len();
+            let repay_amount = ix.data
+                .get(disc_len..disc_len + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+            require!(repay_amount == Some(amount), StableError::FlashRepayMismatch);
+            return Ok(());
+        }
+        index += 1;
+    }
+
+    err!(StableError::FlashRepayMissing)
+}
+
+#[derive(Accounts)]
+pub struct UpdateFlashMintParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct FlashMint<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = borrower_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub borrower_stable_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA for minting stablecoins
+    #[account(
+        seeds = [b"mint_authority"],
+        bump
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    /// CHECK: instructions sysvar, checked by address
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = borrower_stable_account.owner == borrower.key(),
+        constraint = borrower_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub borrower_stable_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::InstructionData;
+    use crate::test_utils::{self, assert_error, pda, run_instruction, TestAccount};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn flash_mint_ix(amount: u64) -> (Pubkey, Vec<u8>) {
+        (crate::ID, crate::instruction::FlashMint { amount }.data())
+    }
+
+    fn flash_repay_ix(amount: u64) -> (Pubkey, Vec<u8>) {
+        (crate::ID, crate::instruction::FlashRepay { amount }.data())
+    }
+
+    /// Flash mint `amount` as instruction `current` of `transaction`, returning the global state
+    fn flash_mint_in(
+        global_state: &GlobalState,
+        amount: u64,
+        transaction: &[(Pubkey, Vec<u8>)],
+        current: u16,
+    ) -> Result<GlobalState> {
+        let borrower = Pubkey::new_unique();
+        let mut accounts = [
+            TestAccount::signer(borrower),
+            TestAccount::state(pda(&[SEED_GLOBAL]), global_state),
+            TestAccount::mint(global_state.stablecoin_mint, pda(&[b"mint_authority"]), 0),
+            TestAccount::token(Pubkey::new_unique(), global_state.stablecoin_mint, borrower, 0),
+            TestAccount::new(pda(&[b"mint_authority"]), anchor_lang::system_program::ID, Vec::new()).read_only(),
+            TestAccount::instructions_sysvar(transaction, current),
+            TestAccount::program(anchor_spl::token::ID),
+        ];
+
+        run_instruction!(FlashMint, accounts, |ctx| flash_mint(ctx, amount))?;
+        Ok(accounts[1].load())
+    }
+
+    fn flash_repay_with(global_state: &GlobalState, amount: u64) -> Result<GlobalState> {
+        let borrower = Pubkey::new_unique();
+        let mut accounts = [
+            TestAccount::signer(borrower),
+            TestAccount::state(pda(&[SEED_GLOBAL]), global_state),
+            TestAccount::mint(global_state.stablecoin_mint, pda(&[b"mint_authority"]), amount),
+            TestAccount::token(Pubkey::new_unique(), global_state.stablecoin_mint, borrower, amount),
+            TestAccount::token(pda(&[SEED_TREASURY]), global_state.stablecoin_mint, pda(&[SEED_TREASURY_AUTHORITY]), 0),
+            TestAccount::program(anchor_spl::token::ID),
+        ];
+
+        run_instruction!(FlashRepay, accounts, |ctx| flash_repay(ctx, amount))?;
+        Ok(accounts[1].load())
+    }
+
+    fn global_state() -> GlobalState {
+        GlobalState {
+            flash_mint_cap: 1_000_000,
+            flash_mint_fee_bps: 9,
+            ..test_utils::global_state(Pubkey::new_unique(), Pubkey::new_unique())
+        }
+    }
+
+    #[test]
+    fn flash_mint_needs_a_matching_repay_later_in_the_transaction() {
+        test_utils::set_time(NOW);
+        let other_program = (Pubkey::new_unique(), flash_repay_ix(500).1);
+
+        let transaction = [flash_mint_ix(500), other_program.clone(), flash_repay_ix(500)];
+        let minted = flash_mint_in(&global_state(), 500, &transaction, 0).unwrap();
+        assert_eq!(minted.flash_minted, 500);
+
+        // a repay of another program, another amount or before the mint doesn't count
+        let transaction = [flash_mint_ix(500), other_program];
+        assert_error(flash_mint_in(&global_state(), 500, &transaction, 0), StableError::FlashRepayMissing);
+
+        let transaction = [flash_mint_ix(500), flash_repay_ix(499)];
+        assert_error(flash_mint_in(&global_state(), 500, &transaction, 0), StableError::FlashRepayMismatch);
+
+        let transaction = [flash_repay_ix(500), flash_mint_ix(500)];
+        assert_error(flash_mint_in(&global_state(), 500, &transaction, 1), StableError::FlashRepayMissing);
+    }
+
+    #[test]
+    fn flash_mint_is_rejected_under_cpi() {
+        test_utils::set_time(NOW);
+        let transaction = [flash_mint_ix(500), flash_repay_ix(500)];
+
+        test_utils::set_stack_height(TRANSACTION_LEVEL_STACK_HEIGHT as u64 + 1);
+        let result = flash_mint_in(&global_state(), 500, &transaction, 0);
+        test_utils::set_stack_height(TRANSACTION_LEVEL_STACK_HEIGHT as u64);
+
+        assert_error(result, StableError::FlashMintCpiNotAllowed);
+    }
+
+    #[test]
+    fn flash_mint_respects_the_cap_and_outstanding_mints() {
+        test_utils::set_time(NOW);
+        let transaction = [flash_mint_ix(1_000_001), flash_repay_ix(1_000_001)];
+        assert_error(
+            flash_mint_in(&global_state(), 1_000_001, &transaction, 0),
+            StableError::FlashMintCapExceeded
+        );
+
+        let outstanding = GlobalState { flash_minted: 1, ..global_state() };
+        let transaction = [flash_mint_ix(500), flash_repay_ix(500)];
+        assert_error(flash_mint_in(&outstanding, 500, &transaction, 0), StableError::FlashMintOutstanding);
+    }
+
+    #[test]
+    fn flash_repay_closes_the_mint_and_collects_the_fee() {
+        test_utils::set_time(NOW);
+        let transaction = [flash_mint_ix(1_000_000), flash_repay_ix(1_000_000)];
+        let minted = flash_mint_in(&global_state(), 1_000_000, &transaction, 0).unwrap();
+
+        assert_error(flash_repay_with(&minted, 999_999), StableError::FlashRepayMismatch);
+        let repaid = flash_repay_with(&minted, 1_000_000).unwrap();
+        assert_eq!(repaid.flash_minted, 0);
+        assert_eq!(repaid.total_fees_collected, 900);
+    }
+}
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::states::*;
 use crate::constants::*;
+use crate::instructions::validate_global_params;
+use crate::utils::WAD;
 
 pub fn initialize_global_state(
     ctx: Context<InitializeGlobalState>,
@@ -8,6 +10,8 @@ pub fn initialize_global_state(
     stability_fee: u64,
     liquidation_penalty: u64,
 ) -> Result<()> {
+    validate_global_params(debt_ceiling, stability_fee, liquidation_penalty)?;
+
     let global_state = &mut ctx.accounts.global_state;
 
     global_state.admin = ctx.accounts.admin.key();
@@ -19,6 +23,24 @@ pub fn initialize_global_state(
     global_state.liquidation_penalty = liquidation_penalty;
     global_state.pool_count = 0;
     global_state.bump = ctx.bumps.global_state;
+    global_state.pending_admin = Pubkey::default();
+    global_state.risk_admin = ctx.accounts.admin.key();
+    global_state.oracle_admin = ctx.accounts.admin.key();
+    global_state.guardian = ctx.accounts.admin.key();
+    global_state.paused_operations = 0;
+    global_state.is_shutdown = false;
+    global_state.shutdown_time = 0;
+    global_state.settlement_finalized = false;
+    global_state.settlement_supply = 0;
+    global_state.total_fees_collected = 0;
+    global_state.bad_debt = 0;
+    global_state.auction_debt = 0;
+    global_state.flash_mint_fee_bps = 0;
+    global_state.flash_mint_cap = 0;
+    global_state.flash_minted = 0;
+    global_state.cross_rate_index = WAD;
+    global_state.cross_last_accrual = Clock::get()?.unix_timestamp;
+    global_state.cross_normalized_debt = 0;
 
     Ok(())
 }
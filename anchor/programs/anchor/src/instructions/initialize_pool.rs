@@ -4,17 +4,20 @@ use crate::constants::*;
 use crate::states::*;
 use crate::errors::*;
 use crate::events::*;
+use crate::utils::*;
 
 pub fn initialize_pool(
     ctx: Context<InitializePool>,
     collateral_factor: u64,
     liquidation_factor: u64,
     interest_rate_model: Pubkey,
+    oracle_config: OracleConfig,
 ) -> Result<()> {
     require!(collateral_factor > 0, StableError::InvalidParameter);
     require!(liquidation_factor > 0, StableError::InvalidParameter);
-    // LTV (collateral_factor) must be lower than liquidation threshold
-    require!(collateral_factor < liquidation_factor, StableError::InvalidParameter);
+    // both are min collateral ratios: a vault opened at collateral_factor must not be liquidatable
+    require!(liquidation_factor <= collateral_factor, StableError::InvalidParameter);
+    validate_oracle_config(&oracle_config)?;
     
     let global_state = &mut ctx.accounts.global_state;
     let pool_registry = &mut ctx.accounts.pool_registry;
@@ -29,6 +32,7 @@ pub fn initialize_pool(
         global_state.admin == ctx.accounts.admin.key(),
         StableError::Unauthorized
     );
+    require!(!global_state.is_shutdown, StableError::SystemShutdown);
     
     // Initialize pool
     pool.mint = ctx.accounts.collateral_mint.key();
@@ -38,7 +42,28 @@ pub fn initialize_pool(
     pool.liquidation_factor = liquidation_factor;
     pool.interest_rate_model = interest_rate_model;
     pool.is_active = true;
+    pool.reduce_only = false;
     pool.bump = ctx.bumps.pool;
+    pool.oracle = oracle_config;
+    pool.rate_index = WAD;
+    pool.last_accrual = Clock::get()?.unix_timestamp;
+    pool.total_normalized_debt = 0;
+    pool.is_caged = false;
+    pool.final_price = 0;
+    pool.settled_collateral = 0;
+    pool.settlement_shortfall = 0;
+    pool.protocol_liquidation_share = 0;
+    pool.liquidation_penalty_bps = global_state.liquidation_penalty;
+    pool.close_factor_bps = DEFAULT_CLOSE_FACTOR_BPS;
+    pool.debt_dust = DEFAULT_DEBT_DUST;
+    pool.auction = AuctionConfig::default();
+    pool.auction_collateral = 0;
+    pool.redemption_fee_bps = 0;
+
+    let mut sorted_vaults = ctx.accounts.sorted_vaults.load_init()?;
+    sorted_vaults.pool = pool.key();
+    sorted_vaults.len = 0;
+    sorted_vaults.bump = ctx.bumps.sorted_vaults;
     
     // Add to registry
     pool_registry.pools.push(pool.key());
@@ -92,6 +117,15 @@ pub struct InitializePool<'info> {
         bump
     )]
     pub pool: Account<'info, CollateralPool>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<SortedVaults>(),
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
     
     pub system_program: Program<'info, System>,
 }
\ No newline at end of file
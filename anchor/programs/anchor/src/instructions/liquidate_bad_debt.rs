@@ -0,0 +1,432 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::events::{BadDebtHealed, BadDebtLiquidated};
+use crate::states::*;
+use crate::utils::*;
+
+/// Fully liquidate a vault whose collateral is worth less than its debt.
+/// The liquidator takes all remaining collateral for its value less the liquidation penalty.
+/// The rest of the debt is covered, in order, by the liquidator buying collateral from the
+/// pool reserve on the same terms, by burning treasury WUSD, and whatever is left is
+/// recorded as protocol bad debt.
+pub fn liquidate_bad_debt(ctx: Context<LiquidateBadDebt>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+
+    let pool_key = ctx.accounts.pool.key();
+    let user_key = ctx.accounts.user_vault.owner;
+    let liquidator_key = ctx.accounts.liquidator.key();
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+
+    let pool = &mut ctx.accounts.pool;
+    let user_vault = &mut ctx.accounts.user_vault;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let total_debt = sync_vault_debt(user_vault, pool)?;
+    require!(total_debt > 0, StableError::InvalidParameter);
+
+    // solvency is judged at the oracle price, the liquidator pays at price + conf
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, pool)?;
+    let collateral_decimals = ctx.accounts.collateral_mint.decimals;
+
+    let shares_to_take = user_vault.collateral_shares;
+    let collateral_to_take = if shares_to_take == 0 {
+        0
+    } else {
+        calculate_amount_from_shares(shares_to_take, pool.total_collateral, pool.total_shares)?
+    };
+
+    let collateral_value = calculate_collateral_value(collateral_to_take, oracle_price.price, collateral_decimals)?;
+    require!(collateral_value < total_debt, StableError::VaultNotUnderwater);
+
+    // the liquidator's bonus is the liquidation penalty applied to what they pay
+    let payout_value = calculate_collateral_value(
+        collateral_to_take,
+        oracle_price.max_price()?,
+        collateral_decimals,
+    )?;
+    let stable_paid = ((payout_value as u128)
+        .checked_mul(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div((BASIS_POINTS_DIVISOR + pool.liquidation_penalty_bps) as u128)
+        .ok_or(StableError::Overflow)? as u64)
+        .min(total_debt);
+
+    let shortfall = total_debt
+        .checked_sub(stable_paid)
+        .ok_or(StableError::Overflow)?;
+
+    // the pool reserve may not exist yet, its address is checked by seeds
+    let reserve_balance = if ctx.accounts.pool_reserve.data_is_empty() {
+        0
+    } else {
+        TokenAccount::try_deserialize(&mut &ctx.accounts.pool_reserve.data.borrow()[..])?.amount
+    };
+    let (covered_by_reserve, reserve_collateral) = if shortfall > 0 && reserve_balance > 0 {
+        let discounted_price = ((oracle_price.max_price()? as u128)
+            .checked_mul(BASIS_POINTS_DIVISOR as u128)
+            .ok_or(StableError::Overflow)?
+            .checked_div((BASIS_POINTS_DIVISOR + pool.liquidation_penalty_bps) as u128)
+            .ok_or(StableError::Overflow)?) as i64;
+        let reserve_value = calculate_collateral_value(reserve_balance, discounted_price, collateral_decimals)?;
+        let covered = shortfall.min(reserve_value);
+        let collateral = calculate_collateral_amount(covered, discounted_price, collateral_decimals)?
+            .min(reserve_balance);
+        (covered, collateral)
+    } else {
+        (0, 0)
+    };
+
+    let remaining_shortfall = shortfall
+        .checked_sub(covered_by_reserve)
+        .ok_or(StableError::Overflow)?;
+    let covered_by_treasury = remaining_shortfall.min(ctx.accounts.treasury.amount);
+    let new_bad_debt = remaining_shortfall
+        .checked_sub(covered_by_treasury)
+        .ok_or(StableError::Overflow)?;
+
+    // clear the vault completely
+    user_vault.collateral_shares = 0;
+    pool.total_collateral = pool.total_collateral
+        .checked_sub(collateral_to_take)
+        .ok_or(StableError::Overflow)?;
+    pool.total_shares = pool.total_shares
+        .checked_sub(shares_to_take)
+        .ok_or(StableError::Overflow)?;
+
+    repay_vault_debt(user_vault, pool, global_state, total_debt)?;
+
+    global_state.bad_debt = global_state.bad_debt
+        .checked_add(new_bad_debt)
+        .ok_or(StableError::Overflow)?;
+
+    user_vault.last_update = current_time;
+
+    // liquidator pays for the vault and reserve collateral, the treasury absorbs what it can of the rest
+    let liquidator_paid = stable_paid
+        .checked_add(covered_by_reserve)
+        .ok_or(StableError::Overflow)?;
+    if liquidator_paid > 0 {
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stable_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            liquidator_paid,
+        )?;
+    }
+
+    let treasury_seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+    if reserve_collateral > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_reserve.to_account_info(),
+                    to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&treasury_seeds[..]],
+            ),
+            reserve_collateral,
+        )?;
+    }
+
+    if covered_by_treasury > 0 {
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&treasury_seeds[..]],
+            ),
+            covered_by_treasury,
+        )?;
+    }
+
+    if collateral_to_take > 0 {
+        let seeds = &[SEED_POOL, ctx.accounts.pool.mint.as_ref(), &[ctx.accounts.pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            collateral_to_take,
+        )?;
+    }
+
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
+
+    emit!(BadDebtLiquidated {
+        liquidator: liquidator_key,
+        user: user_key,
+        pool: pool_key,
+        debt_cleared: total_debt,
+        stable_paid,
+        collateral_taken: collateral_to_take,
+        covered_by_reserve,
+        reserve_collateral,
+        covered_by_treasury,
+        bad_debt: new_bad_debt,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Burn treasury WUSD against recorded bad debt (admin only)
+pub fn heal_bad_debt(ctx: Context<HealBadDebt>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+
+    let healed = reduce_bad_debt(&mut ctx.accounts.global_state, amount)?;
+    require!(
+        ctx.accounts.treasury.amount >= healed,
+        StableError::InsufficientTreasuryBalance
+    );
+
+    let treasury_seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+    token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            },
+            &[&treasury_seeds[..]],
+        ),
+        healed,
+    )?;
+
+    emit!(BadDebtHealed {
+        payer: ctx.accounts.admin.key(),
+        from_treasury: true,
+        amount: healed,
+        remaining_bad_debt: ctx.accounts.global_state.bad_debt,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Burn the caller's WUSD against recorded bad debt, recapitalizing the protocol.
+/// Burns at most the outstanding bad debt.
+pub fn recapitalize(ctx: Context<Recapitalize>, amount: u64) -> Result<()> {
+    let healed = reduce_bad_debt(&mut ctx.accounts.global_state, amount)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.payer_stable_account.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            },
+        ),
+        healed,
+    )?;
+
+    emit!(BadDebtHealed {
+        payer: ctx.accounts.payer.key(),
+        from_treasury: false,
+        amount: healed,
+        remaining_bad_debt: ctx.accounts.global_state.bad_debt,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Take up to `amount` off the recorded bad debt, returns the amount taken off
+fn reduce_bad_debt(global_state: &mut GlobalState, amount: u64) -> Result<u64> {
+    let healed = amount.min(global_state.bad_debt);
+    require!(healed > 0, StableError::InvalidParameter);
+
+    global_state.bad_debt = global_state.bad_debt
+        .checked_sub(healed)
+        .ok_or(StableError::Overflow)?;
+
+    Ok(healed)
+}
+
+#[derive(Accounts)]
+pub struct LiquidateBadDebt<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+        constraint = pool_registry.pools.contains(&pool.key()) @ StableError::InvalidPool
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, collateral_mint.key().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_VAULT, user_vault.owner.as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = liquidator_stable_account.owner == liquidator.key(),
+        constraint = liquidator_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub liquidator_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = liquidator_collateral_account.owner == liquidator.key(),
+        constraint = liquidator_collateral_account.mint == collateral_mint.key(),
+    )]
+    pub liquidator_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: pool collateral reserve, may not be initialized; read as a token account when it holds data
+    #[account(
+        mut,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_reserve: AccountInfo<'info>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct HealBadDebt<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Recapitalize<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = payer_stable_account.owner == payer.key(),
+        constraint = payer_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub payer_stable_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
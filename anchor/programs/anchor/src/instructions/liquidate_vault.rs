@@ -3,25 +3,32 @@ use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer, Burn};
 
 use crate::constants::*;
 use crate::errors::StableError;
-use crate::events::LiquidateEvent;
+use crate::events::{LiquidateEvent, StabilityPoolAbsorbed};
 use crate::states::*;
-use crate::utils::oracle::*;
+use crate::utils::*;
 
 pub fn liquidate_vault(
     ctx: Context<LiquidateVault>,
     debt_to_repay: u64,
 ) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_LIQUIDATE),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
     require!(debt_to_repay > 0, StableError::InvalidParameter);
     
     let pool_key = ctx.accounts.pool.key();
     let user_key = ctx.accounts.user_vault.owner;
     let liquidator_key = ctx.accounts.liquidator.key();
     
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+    
     let pool = &ctx.accounts.pool;
     let user_vault = &mut ctx.accounts.user_vault;
     let global_state = &mut ctx.accounts.global_state;
     
-    require!(pool.is_active, StableError::PoolNotActive);
     require!(
         user_vault.pool == pool_key,
         StableError::InvalidPool
@@ -31,11 +38,11 @@ pub fn liquidate_vault(
         StableError::InvalidPool
     );
     
-    // get current price from oracle
-    let price_data = ctx.accounts.price_feed.data.borrow();
-    let (price, _confidence) = parse_pyth_price(&price_data)?;
-    require!(price > 0, StableError::InvalidOracle);
-    let price_u128 = price as u128;
+    // get current price from oracle - same resolution path as mint_stable
+    // health is judged at the oracle price, seized collateral is sized at price + conf
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, pool)?;
+    let price = oracle_price.price;
+    let payout_price = oracle_price.max_price()?;
     
     let collateral_amount = ((user_vault.collateral_shares as u128)
         .checked_mul(pool.total_collateral as u128)
@@ -43,46 +50,15 @@ pub fn liquidate_vault(
         .checked_div((pool.total_shares as u128).max(1))
         .ok_or(StableError::Overflow)?) as u64;
     
-    // calculate collateral value
+    // calculate collateral value in stablecoin base units (6 decimals)
     let collateral_decimals = ctx.accounts.collateral_mint.decimals;
-    let price_decimals: u32 = 8;
-    let stable_decimals: u32 = 6;
-
-    // Value in stablecoin base units (6 decimals), aligned with mint_stable math.
-    let collateral_value = ((collateral_amount as u128)
-        .checked_mul(price_u128)
-        .ok_or(StableError::Overflow)?
-        .checked_div(10u128.pow(collateral_decimals as u32))
-        .ok_or(StableError::Overflow)?
-        .checked_div(10u128.pow(price_decimals - stable_decimals))
-        .ok_or(StableError::Overflow)?) as u64;
+    let collateral_value = calculate_collateral_value(collateral_amount, price, collateral_decimals)?;
     
-    let current_time = Clock::get()?.unix_timestamp;
-    let time_elapsed = current_time - user_vault.last_update;
-    
-    let new_interest = if time_elapsed > 0 && user_vault.debt_amount > 0 {
-        let apr_bps = global_state.stability_fee;
-        let interest_rate = (apr_bps as f64) / (BASIS_POINTS_DIVISOR as f64);
-        let per_second_rate = interest_rate / 31536000.0;
-        
-        ((user_vault.debt_amount as f64) * per_second_rate * (time_elapsed as f64)) as u64
-    } else {
-        0
-    };
-    
-    // calculate total debt
-    let total_debt = user_vault.debt_amount
-        .checked_add(user_vault.accrued_interest)
-        .ok_or(StableError::Overflow)?
-        .checked_add(new_interest)
-        .ok_or(StableError::Overflow)?;
+    // calculate total debt at the pool's current rate index
+    let total_debt = sync_vault_debt(user_vault, pool)?;
     
     // calculate collateral ratio
-    let collateral_ratio = ((collateral_value as u128)
-        .checked_mul(BASIS_POINTS_DIVISOR as u128)
-        .ok_or(StableError::Overflow)?
-        .checked_div((total_debt as u128).max(1))
-        .ok_or(StableError::Overflow)?) as u64;
+    let collateral_ratio = calculate_collateral_ratio_bps(collateral_value, total_debt)?;
     
     // check if vault is liquidatable
     require!(
@@ -90,30 +66,25 @@ pub fn liquidate_vault(
         StableError::CannotLiquidateHealthyVault
     );
     
-    // limit liquidation amount (max 50% of debt per liquidation)
-    let max_liquidation = total_debt
-        .checked_mul(5000)
+    // limit liquidation amount to the pool's close factor
+    let close_factor_amount = ((total_debt as u128)
+        .checked_mul(pool.close_factor_bps as u128)
         .ok_or(StableError::Overflow)?
-        .checked_div(BASIS_POINTS_DIVISOR)
-        .ok_or(StableError::Overflow)?;
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+
+    // allow a full liquidation when the close factor would leave less than dust behind
+    let max_liquidation = if total_debt - close_factor_amount < pool.debt_dust {
+        total_debt
+    } else {
+        close_factor_amount
+    };
     
     let actual_debt_to_repay = debt_to_repay.min(max_liquidation).min(total_debt);
     require!(actual_debt_to_repay > 0, StableError::InvalidParameter);
     
-    // burn liquidators stablecoins
-    let burn_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Burn {
-            mint: ctx.accounts.stablecoin_mint.to_account_info(),
-            from: ctx.accounts.liquidator_stable_account.to_account_info(),
-            authority: ctx.accounts.liquidator.to_account_info(),
-        },
-    );
-    
-    token::burn(burn_ctx, actual_debt_to_repay)?;
-    
     // calculate collateral to give (with liq bonus)
-    let liquidation_penalty_bps = global_state.liquidation_penalty; 
+    let liquidation_penalty_bps = pool.liquidation_penalty_bps;
     let collateral_value_to_take = ((actual_debt_to_repay as u128)
         .checked_mul((BASIS_POINTS_DIVISOR + liquidation_penalty_bps) as u128)
         .ok_or(StableError::Overflow)?
@@ -121,13 +92,44 @@ pub fn liquidate_vault(
         .ok_or(StableError::Overflow)?) as u64;
     
     // convert value back to collateral tokens
-    let collateral_to_take = ((collateral_value_to_take as u128)
-        .checked_mul(10u128.pow(ctx.accounts.collateral_mint.decimals as u32))
+    let collateral_to_take = calculate_collateral_amount(
+        collateral_value_to_take,
+        payout_price,
+        collateral_decimals,
+    )?;
+    
+    // the bonus part of the seized collateral is split with the pool reserve
+    let debt_collateral = calculate_collateral_amount(
+        actual_debt_to_repay,
+        payout_price,
+        collateral_decimals,
+    )?;
+    let bonus_collateral = collateral_to_take.saturating_sub(debt_collateral);
+    let protocol_fee = ((bonus_collateral as u128)
+        .checked_mul(pool.protocol_liquidation_share as u128)
         .ok_or(StableError::Overflow)?
-        .checked_mul(10u128.pow(price_decimals - stable_decimals))
-        .ok_or(StableError::Overflow)?
-        .checked_div(price_u128)
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
         .ok_or(StableError::Overflow)?) as u64;
+    let mut liquidator_collateral = collateral_to_take
+        .checked_sub(protocol_fee)
+        .ok_or(StableError::Overflow)?;
+
+    // the pool's stability pool absorbs as much of the debt as it can,
+    // the external liquidator covers the rest
+    let absorbed = absorb_liquidation(
+        load_stability_pool(&ctx.accounts.stability_pool)?,
+        actual_debt_to_repay,
+        liquidator_collateral,
+    )?;
+    let (stability_pool_debt, stability_pool_collateral) = absorbed
+        .as_ref()
+        .map_or((0, 0), |(_, debt, collateral)| (*debt, *collateral));
+    let liquidator_debt = actual_debt_to_repay
+        .checked_sub(stability_pool_debt)
+        .ok_or(StableError::Overflow)?;
+    liquidator_collateral = liquidator_collateral
+        .checked_sub(stability_pool_collateral)
+        .ok_or(StableError::Overflow)?;
     
     // convert to shares
     let shares_to_take = ((collateral_to_take as u128)
@@ -152,17 +154,33 @@ pub fn liquidate_vault(
     
     let pool_info = ctx.accounts.pool.to_account_info();
     
-    let transfer_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.pool_collateral_account.to_account_info(),
-            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
-            authority: pool_info,
-        },
-        signer_seeds,
-    );
-    
-    token::transfer(transfer_ctx, collateral_to_take)?;
+    if liquidator_collateral > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_collateral_account.to_account_info(),
+                to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+                authority: pool_info,
+            },
+            signer_seeds,
+        );
+
+        token::transfer(transfer_ctx, liquidator_collateral)?;
+    }
+
+    if protocol_fee > 0 {
+        let reserve_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_collateral_account.to_account_info(),
+                to: ctx.accounts.pool_reserve.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        token::transfer(reserve_ctx, protocol_fee)?;
+    }
     
     let pool_mut = &mut ctx.accounts.pool;
     
@@ -171,44 +189,13 @@ pub fn liquidate_vault(
         .checked_sub(shares_to_take)
         .ok_or(StableError::Overflow)?;
     
-    let mut remaining_debt_repay = actual_debt_to_repay;
-    let mut interest_paid_total = 0u64;
-
-    if user_vault.accrued_interest > 0 && remaining_debt_repay > 0 {
-        let interest_paid = user_vault.accrued_interest.min(remaining_debt_repay);
-
-        user_vault.accrued_interest = user_vault.accrued_interest
-            .checked_sub(interest_paid)
-            .ok_or(StableError::Overflow)?;
-
-        remaining_debt_repay = remaining_debt_repay
-            .checked_sub(interest_paid)
-            .ok_or(StableError::Overflow)?;
-
-        interest_paid_total = interest_paid_total
-            .checked_add(interest_paid)
-            .ok_or(StableError::Overflow)?;
-    }
-
-    if new_interest > 0 && remaining_debt_repay > 0 {
-        let interest_paid = new_interest.min(remaining_debt_repay);
-
-        remaining_debt_repay = remaining_debt_repay
-            .checked_sub(interest_paid)
-            .ok_or(StableError::Overflow)?;
-
-        interest_paid_total = interest_paid_total
-            .checked_add(interest_paid)
-            .ok_or(StableError::Overflow)?;
-    }
-
-    let principal_paid = remaining_debt_repay.min(user_vault.debt_amount);
-    user_vault.debt_amount = user_vault.debt_amount
-        .checked_sub(principal_paid)
-        .ok_or(StableError::Overflow)?;
+    // pay down interest first, then principal
+    let (_, interest_paid) = repay_vault_debt(user_vault, pool_mut, global_state, actual_debt_to_repay)?;
 
-    global_state.total_debt = global_state.total_debt
-        .checked_sub(principal_paid)
+    // only the liquidator's WUSD is routed to the treasury, stability pool funds are burned
+    let treasury_amount = interest_paid.min(liquidator_debt);
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(treasury_amount)
         .ok_or(StableError::Overflow)?;
 
     pool_mut.total_collateral = pool_mut.total_collateral
@@ -221,10 +208,58 @@ pub fn liquidate_vault(
     
     user_vault.last_update = current_time;
     
+    if let Some((stability_pool, _, _)) = absorbed {
+        stability_pool.try_serialize(&mut &mut ctx.accounts.stability_pool.try_borrow_mut_data()?[..])?;
+
+        emit!(StabilityPoolAbsorbed {
+            pool: pool_key,
+            user: user_key,
+            debt_absorbed: stability_pool_debt,
+            collateral_gained: stability_pool_collateral,
+            total_deposits: stability_pool.total_deposits,
+            timestamp: current_time,
+        });
+
+        absorb_stability_pool_debt(&ctx, stability_pool_debt, stability_pool_collateral, signer_seeds)?;
+    }
+
+    // interest portion of the repayment goes to the treasury, the rest is burned
+    if treasury_amount > 0 {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.liquidator_stable_account.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.liquidator.to_account_info(),
+            },
+        );
+
+        token::transfer(transfer_ctx, treasury_amount)?;
+    }
+
+    let burn_amount = liquidator_debt
+        .checked_sub(treasury_amount)
+        .ok_or(StableError::Overflow)?;
+
+    if burn_amount > 0 {
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.liquidator_stable_account.to_account_info(),
+                authority: ctx.accounts.liquidator.to_account_info(),
+            },
+        );
+
+        token::burn(burn_ctx, burn_amount)?;
+    }
+    
     let bonus = collateral_value_to_take
         .checked_sub(actual_debt_to_repay)
         .ok_or(StableError::Overflow)?;
     
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
+
     emit!(LiquidateEvent {
         liquidator: liquidator_key,
         user: user_key,
@@ -232,12 +267,61 @@ pub fn liquidate_vault(
         debt_repaid: actual_debt_to_repay,
         collateral_taken: collateral_to_take,
         bonus,
+        protocol_fee,
         timestamp: current_time,
     });
     
     Ok(())
 }
 
+/// Burn `debt` from the stability pool's WUSD and move `collateral` from the pool into it
+fn absorb_stability_pool_debt(
+    ctx: &Context<LiquidateVault>,
+    debt: u64,
+    collateral: u64,
+    pool_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let (Some(stability_pool_stable_account), Some(stability_pool_collateral_account)) = (
+        ctx.accounts.stability_pool_stable_account.as_ref(),
+        ctx.accounts.stability_pool_collateral_account.as_ref(),
+    ) else {
+        return err!(StableError::StabilityPoolAccountsMissing);
+    };
+
+    let pool_key = ctx.accounts.pool.key();
+    let stability_pool_seeds = &[SEED_STABILITY_POOL, pool_key.as_ref(), &[ctx.bumps.stability_pool]];
+
+    token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: stability_pool_stable_account.to_account_info(),
+                authority: ctx.accounts.stability_pool.to_account_info(),
+            },
+            &[&stability_pool_seeds[..]],
+        ),
+        debt,
+    )?;
+
+    if collateral > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: stability_pool_collateral_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                pool_signer_seeds,
+            ),
+            collateral,
+        )?;
+    }
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct LiquidateVault<'info> {
     #[account(mut)]
@@ -271,12 +355,19 @@ pub struct LiquidateVault<'info> {
     
     #[account(
         mut,
-        seeds = [SEED_VAULT, user_vault.owner.as_ref(), pool.key().as_ref()],
+        seeds = [SEED_VAULT, user_vault.owner.as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
         bump
     )]
     pub user_vault: Box<Account<'info, UserVault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
     
-    /// CHECK: Pyth price feed account - validated by parse_pyth_price
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
     pub price_feed: AccountInfo<'info>,
     
     #[account(
@@ -285,6 +376,13 @@ pub struct LiquidateVault<'info> {
         constraint = liquidator_stable_account.mint == stablecoin_mint.key(),
     )]
     pub liquidator_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
     
     #[account(
         mut,
@@ -299,6 +397,36 @@ pub struct LiquidateVault<'info> {
         token::authority = pool,
     )]
     pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_reserve: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: the pool's stability pool, may not be initialized; read and written by liquidate_vault.
+    /// When it can absorb debt it does so before the liquidator and its token accounts are required.
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_stable_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_collateral_account: Option<Box<Account<'info, TokenAccount>>>,
     
     pub token_program: Program<'info, Token>,
 }
\ No newline at end of file
@@ -0,0 +1,362 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::instructions::pool_admin::close_program_account;
+use crate::states::*;
+use crate::utils::*;
+
+/// GlobalState as deployed before the fields appended since
+#[derive(AnchorDeserialize)]
+struct LegacyGlobalState {
+    admin: Pubkey,
+    stablecoin_mint: Pubkey,
+    governance_token_mint: Pubkey,
+    total_debt: u64,
+    debt_ceiling: u64,
+    stability_fee: u64,
+    liquidation_penalty: u64,
+    pool_count: u64,
+    bump: u8,
+}
+
+/// CollateralPool as deployed before the fields appended since
+#[derive(AnchorDeserialize)]
+struct LegacyCollateralPool {
+    mint: Pubkey,
+    total_collateral: u64,
+    total_shares: u64,
+    collateral_factor: u64,
+    liquidation_factor: u64,
+    interest_rate_model: Pubkey,
+    is_active: bool,
+    bump: u8,
+}
+
+/// UserVault as deployed before normalized debt and vault indices, at [SEED_VAULT, owner, pool]
+#[derive(AnchorDeserialize)]
+struct LegacyUserVault {
+    owner: Pubkey,
+    pool: Pubkey,
+    collateral_shares: u64,
+    debt_amount: u64,
+    accrued_interest: u64,
+    last_update: i64,
+}
+
+/// Grow the deployed global state to the current layout (admin only).
+/// Roles default to the admin, everything added since starts empty.
+pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+    let info = ctx.accounts.global_state.to_account_info();
+    let legacy: LegacyGlobalState = read_legacy_account(&info, GlobalState::DISCRIMINATOR, GlobalState::INIT_SPACE)?;
+    require!(legacy.admin == ctx.accounts.admin.key(), StableError::Unauthorized);
+
+    let admin = legacy.admin;
+    let global_state = GlobalState {
+        admin,
+        stablecoin_mint: legacy.stablecoin_mint,
+        governance_token_mint: legacy.governance_token_mint,
+        // principal only, migrate_vault adds each vault's interest
+        total_debt: legacy.total_debt,
+        debt_ceiling: legacy.debt_ceiling,
+        stability_fee: legacy.stability_fee,
+        liquidation_penalty: legacy.liquidation_penalty,
+        pool_count: legacy.pool_count,
+        bump: legacy.bump,
+        pending_admin: Pubkey::default(),
+        risk_admin: admin,
+        oracle_admin: admin,
+        guardian: admin,
+        paused_operations: 0,
+        is_shutdown: false,
+        shutdown_time: 0,
+        settlement_finalized: false,
+        settlement_supply: 0,
+        total_fees_collected: 0,
+        bad_debt: 0,
+        auction_debt: 0,
+        flash_mint_fee_bps: 0,
+        flash_mint_cap: 0,
+        flash_minted: 0,
+        cross_rate_index: WAD,
+        cross_last_accrual: Clock::get()?.unix_timestamp,
+        cross_normalized_debt: 0,
+    };
+
+    grow_account(&info, &ctx.accounts.admin, &ctx.accounts.system_program, GlobalState::INIT_SPACE)?;
+    global_state.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+/// Grow a deployed pool to the current layout (admin only).
+/// Liquidation settings get the defaults of `initialize_pool`, the pool starts at a rate index of 1.0
+/// and picks up its vaults' debt as they are migrated. Its sorted vault index is created
+/// afterwards with `initialize_sorted_vaults`.
+pub fn migrate_pool(ctx: Context<MigratePool>, oracle_config: OracleConfig) -> Result<()> {
+    let global_state = &ctx.accounts.global_state;
+    require!(
+        global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    validate_oracle_config(&oracle_config)?;
+
+    let info = ctx.accounts.pool.to_account_info();
+    let legacy: LegacyCollateralPool = read_legacy_account(&info, CollateralPool::DISCRIMINATOR, CollateralPool::INIT_SPACE)?;
+
+    let pool = CollateralPool {
+        mint: legacy.mint,
+        total_collateral: legacy.total_collateral,
+        total_shares: legacy.total_shares,
+        collateral_factor: legacy.collateral_factor,
+        liquidation_factor: legacy.liquidation_factor,
+        interest_rate_model: legacy.interest_rate_model,
+        is_active: legacy.is_active,
+        bump: legacy.bump,
+        reduce_only: false,
+        oracle: oracle_config,
+        rate_index: WAD,
+        last_accrual: Clock::get()?.unix_timestamp,
+        total_normalized_debt: 0,
+        is_caged: false,
+        final_price: 0,
+        settled_collateral: 0,
+        settlement_shortfall: 0,
+        protocol_liquidation_share: 0,
+        liquidation_penalty_bps: global_state.liquidation_penalty,
+        close_factor_bps: DEFAULT_CLOSE_FACTOR_BPS,
+        debt_dust: DEFAULT_DEBT_DUST,
+        auction: AuctionConfig::default(),
+        auction_collateral: 0,
+        redemption_fee_bps: 0,
+    };
+
+    grow_account(&info, &ctx.accounts.admin, &ctx.accounts.system_program, CollateralPool::INIT_SPACE)?;
+    pool.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(PoolMigrated {
+        admin: ctx.accounts.admin.key(),
+        pool: info.key(),
+        collateral_mint: legacy.mint,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Move a vault from the old [SEED_VAULT, owner, pool] address to the owner's vault number
+/// `vault_index`, which has to be the next index of the owner's vault counter (anyone can call,
+/// so keepers can migrate every vault before settlement). The debt is compounded at the stability
+/// fee since the vault was last touched, like the pool's rate index, and carried over as normalized
+/// debt. The old vault's rent goes back to its owner. `sync_sorted_vault` puts the new vault into
+/// the pool's index.
+pub fn migrate_vault(ctx: Context<MigrateVault>, vault_index: u16) -> Result<()> {
+    let legacy_info = ctx.accounts.legacy_vault.to_account_info();
+    let legacy: LegacyUserVault = read_legacy_account(&legacy_info, UserVault::DISCRIMINATOR, UserVault::INIT_SPACE)?;
+
+    let owner = ctx.accounts.owner.key();
+    let pool_key = ctx.accounts.pool.key();
+    require!(
+        legacy.owner == owner && legacy.pool == pool_key,
+        StableError::InvalidPool
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let global_state = &mut ctx.accounts.global_state;
+    accrue_pool_interest(pool, global_state, current_time)?;
+
+    // compound what was owed at the last update, the same way the rate index grows
+    let owed_at_last_update = legacy.debt_amount
+        .checked_add(legacy.accrued_interest)
+        .ok_or(StableError::Overflow)?;
+    let factor = compound_factor(global_state.stability_fee, current_time - legacy.last_update)?;
+    let owed = u64::try_from(wad_mul(owed_at_last_update as u128, factor)?)
+        .map_err(|_| StableError::Overflow)?;
+    let interest = owed
+        .checked_sub(legacy.debt_amount)
+        .ok_or(StableError::Overflow)?;
+
+    let normalized = debt_to_normalized(owed, pool.rate_index, true)?;
+    pool.total_normalized_debt = pool.total_normalized_debt
+        .checked_add(normalized)
+        .ok_or(StableError::Overflow)?;
+    // the old global total only tracked principal
+    global_state.total_debt = global_state.total_debt
+        .checked_add(interest)
+        .ok_or(StableError::Overflow)?;
+
+    let vault_counter = &mut ctx.accounts.vault_counter;
+    if vault_counter.owner == Pubkey::default() {
+        vault_counter.owner = owner;
+        vault_counter.bump = ctx.bumps.vault_counter;
+    }
+    require!(vault_index == vault_counter.next_index, StableError::InvalidVaultIndex);
+    vault_counter.next_index = vault_counter.next_index
+        .checked_add(1)
+        .ok_or(StableError::Overflow)?;
+
+    let user_vault = &mut ctx.accounts.user_vault;
+    user_vault.owner = owner;
+    user_vault.pool = pool_key;
+    user_vault.collateral_shares = legacy.collateral_shares;
+    user_vault.debt_amount = legacy.debt_amount;
+    user_vault.normalized_debt = u64::try_from(normalized).map_err(|_| StableError::Overflow)?;
+    user_vault.last_update = current_time;
+    user_vault.bump = ctx.bumps.user_vault;
+    user_vault.vault_index = vault_index;
+    sync_vault_debt(user_vault, pool)?;
+
+    close_program_account(&legacy_info, &ctx.accounts.owner)?;
+
+    emit!(VaultMigrated {
+        migrator: ctx.accounts.payer.key(),
+        user: owner,
+        pool: pool_key,
+        legacy_vault: legacy_info.key(),
+        vault: user_vault.key(),
+        vault_index,
+        debt: owed,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Read an account still in its deployed layout: it carries the account discriminator
+/// but is shorter than the current `space`
+fn read_legacy_account<T: AnchorDeserialize>(info: &AccountInfo, discriminator: &[u8], space: usize) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, StableError::InvalidParameter);
+
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= discriminator.len() && data[..discriminator.len()] == *discriminator,
+        StableError::InvalidParameter
+    );
+    require!(data.len() < 8 + space, StableError::AccountAlreadyMigrated);
+
+    T::deserialize(&mut &data[discriminator.len()..]).map_err(Into::into)
+}
+
+/// Realloc an account to `8 + space`, topping up its rent from `payer`
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let new_len = 8 + space;
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let top_up = rent.saturating_sub(info.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+
+    info.resize(new_len)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: global state in its deployed layout, read by migrate_global_state
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump
+    )]
+    pub global_state: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: only used for the pool seeds
+    pub collateral_mint: AccountInfo<'info>,
+
+    /// CHECK: pool in its deployed layout, read by migrate_pool
+    #[account(
+        mut,
+        seeds = [SEED_POOL, collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u16)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: owner of the legacy vault, checked against its data by migrate_vault. Gets its rent back.
+    #[account(mut)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    /// CHECK: vault at its old address and in its deployed layout, read by migrate_vault
+    #[account(
+        mut,
+        seeds = [SEED_VAULT, owner.key().as_ref(), pool.key().as_ref()],
+        bump
+    )]
+    pub legacy_vault: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + UserVault::INIT_SPACE,
+        seeds = [SEED_VAULT, owner.key().as_ref(), pool.key().as_ref(), vault_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserVaultCounter::INIT_SPACE,
+        seeds = [SEED_VAULT_COUNTER, owner.key().as_ref()],
+        bump
+    )]
+    pub vault_counter: Account<'info, UserVaultCounter>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -4,10 +4,14 @@ use crate::states::*;
 use anchor_spl::token::{self, MintTo, Mint, TokenAccount, Token};
 use crate::errors::*;
 use crate::events::*;
-use crate::utils::oracle::*;
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
+use crate::utils::*;
 
 pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_MINT),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
     require!(stable_amount > 0, StableError::InvalidParameter);
 
     let pool_key  = ctx.accounts.pool.key();
@@ -18,11 +22,17 @@ pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
     let global_state = &mut ctx.accounts.global_state;
 
     require!(pool.is_active, StableError::PoolNotActive);
+    require!(!pool.reduce_only, StableError::PoolReduceOnly);
     require!(
         user_vault.owner == user_key && user_vault.pool == pool_key,
         StableError::InvalidPool
     );
 
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // bring pool interest into the global total so the ceiling applies to owed debt
+    accrue_pool_interest(pool, global_state, current_time)?;
+
     let new_total_debt = global_state.total_debt
         .checked_add(stable_amount)
         .ok_or(StableError::Overflow)?;
@@ -32,56 +42,11 @@ pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
         StableError::DebtCeilingReached
     );
 
-    // Get price from oracle - try MockPriceFeed first, then Pyth PriceUpdateV2
-    let (price, _confidence) = {
-        let price_data = ctx.accounts.price_feed.data.borrow();
-        
-        match parse_pyth_price(&price_data) {
-            Ok((p, c)) => {
-                msg!("DEBUG: Using MockPriceFeed, price = {}", p);
-                (p, c)
-            },
-            Err(_) => {
-                // Not a mock feed, try Pyth PriceUpdateV2
-                // PriceUpdateV2 uses Anchor's discriminator (first 8 bytes)
-                // Then we can use borsh to deserialize the rest
-                
-                let feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-                    .map_err(|_| StableError::InvalidOracle)?;
-                
-                // Try to deserialize as PriceUpdateV2
-                let mut data_slice: &[u8] = &price_data;
-                let price_update = PriceUpdateV2::try_deserialize(&mut data_slice)
-                    .map_err(|_| StableError::InvalidOracle)?;
-                
-                let clock = Clock::get()?;
-                let pyth_price = price_update
-                    .get_price_no_older_than(&clock, MAXIMUM_AGE, &feed_id)
-                    .map_err(|_| StableError::InvalidOracle)?;
-                
-                // Pyth price is in format: price * 10^exponent
-                // Normalize to 8 decimals for our calculations
-                let exponent = pyth_price.exponent;
-                let scale = 8 + exponent;
-                
-                let normalized_price = if scale >= 0 {
-                    pyth_price.price.checked_mul(10i64.pow(scale as u32)).unwrap_or(pyth_price.price)
-                } else {
-                    pyth_price.price.checked_div(10i64.pow((-scale) as u32)).unwrap_or(pyth_price.price)
-                };
-                
-                let normalized_conf = if scale >= 0 {
-                    pyth_price.conf.checked_mul(10u64.pow(scale as u32)).unwrap_or(pyth_price.conf)
-                } else {
-                    pyth_price.conf.checked_div(10u64.pow((-scale) as u32)).unwrap_or(pyth_price.conf)
-                };
-                
-                msg!("DEBUG: Using Pyth PriceUpdateV2, price = {}, exponent = {}", normalized_price, exponent);
-                (normalized_price, normalized_conf)
-            }
-        }
-    };
-    
+    // Get price from oracle - MockPriceFeed or Pyth PriceUpdateV2
+    // collateral is valued at the low end of the confidence interval
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, pool)?;
+    let price = oracle_price.min_price()?;
+
     // calculate user's collateral amount (in tokens) using u128 to prevent overflow
     let user_collateral_amount = ((user_vault.collateral_shares as u128)
         .checked_mul(pool.total_collateral as u128)
@@ -89,64 +54,27 @@ pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
         .checked_div((pool.total_shares as u128).max(1))
         .ok_or(StableError::Overflow)?) as u64;
 
-    msg!("DEBUG: user_collateral_amount = {}", user_collateral_amount);
-
-    // calculate collateral value in USD (use u128 to avoid overflow)
-    // price has 8 decimals, collateral has 9 decimals (SOL), WUSD has 6 decimals
-    // collateral_value = (collateral_amount * price) / 10^collateral_decimals / 10^(price_decimals - wusd_decimals)
-    // = (collateral_amount * price) / 10^9 / 10^2 = (collateral_amount * price) / 10^11
+    // calculate collateral value in USD (6 decimals, WUSD base units)
     let collateral_decimals = ctx.accounts.collateral_mint.decimals;
-    let price_decimals: u32 = 8;
-    let wusd_decimals: u32 = 6;
-    
-    // Use u128 for intermediate calculation to prevent overflow
-    let collateral_value_u128 = (user_collateral_amount as u128)
-        .checked_mul(price as u128)
-        .ok_or(StableError::Overflow)?
-        .checked_div(10u128.pow(collateral_decimals as u32))
-        .ok_or(StableError::Overflow)?
-        .checked_div(10u128.pow(price_decimals - wusd_decimals))
-        .ok_or(StableError::Overflow)?;
-    
-    let collateral_value = collateral_value_u128 as u64;
-    
-    msg!("DEBUG: collateral_decimals = {}, collateral_value = {}", collateral_decimals, collateral_value);
-
-    let current_time = Clock::get()?.unix_timestamp;
-    let time_elapsed = current_time - user_vault.last_update;
+    let collateral_value = calculate_collateral_value(
+        user_collateral_amount,
+        price,
+        collateral_decimals,
+    )?;
 
-    let interest = if time_elapsed > 0 && user_vault.debt_amount > 0 {
-        let apr_bps = global_state.stability_fee;
-        let interest_rate = (apr_bps as f64) / (BASIS_POINTS_DIVISOR as f64);
-        let per_second_rate = interest_rate / 31536000.0;
-
-        ((user_vault.debt_amount as f64) * per_second_rate * (time_elapsed as f64)) as u64
-    } else {
-        0
-    };
-
-    // calculate total existing debt (principal + accrued + new interest)
-    let total_existing_debt = user_vault.debt_amount
-        .checked_add(user_vault.accrued_interest)
-        .ok_or(StableError::Overflow)?
-        .checked_add(interest)
-        .ok_or(StableError::Overflow)?;
+    // calculate total existing debt (principal + interest at the pool's rate index)
+    let total_existing_debt = sync_vault_debt(user_vault, pool)?;
 
     // calculate new total debt after minting
     let new_total_debt_for_user = total_existing_debt
         .checked_add(stable_amount)
         .ok_or(StableError::Overflow)?;
 
-    msg!("DEBUG: stable_amount = {}, new_total_debt = {}", stable_amount, new_total_debt_for_user);
+    // keep vault debt above dust so the sorted index cannot be filled with tiny vaults
+    require!(new_total_debt_for_user >= pool.debt_dust, StableError::DebtBelowDust);
 
-    // calculate collateral ratio (in bps) using u128 to avoid overflow
-    let collateral_ratio = (collateral_value as u128)
-        .checked_mul(BASIS_POINTS_DIVISOR as u128)
-        .ok_or(StableError::Overflow)?
-        .checked_div((new_total_debt_for_user as u128).max(1))
-        .ok_or(StableError::Overflow)? as u64;
-
-    msg!("DEBUG: collateral_ratio = {} bps, required = {} bps", collateral_ratio, pool.collateral_factor);
+    // calculate collateral ratio (in bps)
+    let collateral_ratio = calculate_collateral_ratio_bps(collateral_value, new_total_debt_for_user)?;
 
     // check minimum collateral ratio
     require!(
@@ -154,22 +82,11 @@ pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
         StableError::CollateralRatioTooLowForMint
     );
 
-    // update vault state
-    // add new interest to accrued
-    user_vault.accrued_interest = user_vault.accrued_interest
-        .checked_add(interest)
-        .ok_or(StableError::Overflow)?;
-
-    // add new debt
-    user_vault.debt_amount = user_vault.debt_amount
-        .checked_add(stable_amount)
-        .ok_or(StableError::Overflow)?;
+    // add new debt to the vault, pool and global totals
+    add_vault_debt(user_vault, pool, global_state, stable_amount)?;
 
     user_vault.last_update = current_time;
 
-    // update global state
-    global_state.total_debt = new_total_debt;
-
     // mint stablecoins to user
     let mint_authority_bump = ctx.bumps.mint_authority;
     let seeds = &[
@@ -191,11 +108,13 @@ pub fn mint_stable(ctx: Context<MintStable>, stable_amount: u64) -> Result<()> {
 
     token::mint_to(mint_ctx, stable_amount)?;
 
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
+
     emit!(MintStableEvent {
         user: user_key,
         pool: pool_key,
         stable_amount,
-        debt_amount: user_vault.debt_amount,
+        debt_amount: ctx.accounts.user_vault.debt_amount,
         collateral_ratio,
         timestamp: current_time,
     });
@@ -239,12 +158,19 @@ pub struct MintStable<'info> {
 
     #[account(
         mut,
-        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref()],
+        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
         bump,
     )]
     pub user_vault: Account<'info, UserVault>,
 
-    /// CHECK: Pyth price feed account - validated by parse_pyth_price
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
     pub price_feed: AccountInfo<'info>,
 
     #[account(
@@ -23,4 +23,55 @@ pub use test_minimal::*;
 pub mod test_minimal;
 
 pub use set_mock_price::*;
-pub mod set_mock_price;
\ No newline at end of file
+pub mod set_mock_price;
+
+pub use update_pool_oracle::*;
+pub mod update_pool_oracle;
+
+pub use update_global_params::*;
+pub mod update_global_params;
+
+pub use pool_admin::*;
+pub mod pool_admin;
+
+pub use admin_roles::*;
+pub mod admin_roles;
+
+pub use set_paused::*;
+pub mod set_paused;
+
+pub use settlement::*;
+pub mod settlement;
+
+pub use treasury::*;
+pub mod treasury;
+
+pub use liquidate_bad_debt::*;
+pub mod liquidate_bad_debt;
+
+pub use auction::*;
+pub mod auction;
+
+pub use stability_pool::*;
+pub mod stability_pool;
+
+pub use redeem::*;
+pub mod redeem;
+
+pub use psm::*;
+pub mod psm;
+
+pub use flash_mint::*;
+pub mod flash_mint;
+
+pub use savings::*;
+pub mod savings;
+
+pub use cross_margin::*;
+pub mod cross_margin;
+
+pub use close_vault::*;
+pub mod close_vault;
+
+pub use migrate::*;
+pub mod migrate;
\ No newline at end of file
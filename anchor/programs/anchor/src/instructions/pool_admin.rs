@@ -0,0 +1,485 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::*;
+
+/// Update the risk factors of a collateral pool (risk admin only)
+pub fn update_pool_params(
+    ctx: Context<UpdatePool>,
+    collateral_factor: u64,
+    liquidation_factor: u64,
+) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    require!(collateral_factor > 0, StableError::InvalidParameter);
+    require!(liquidation_factor > 0, StableError::InvalidParameter);
+    require!(liquidation_factor <= collateral_factor, StableError::InvalidParameter);
+
+    let pool = &mut ctx.accounts.pool;
+
+    let old_collateral_factor = pool.collateral_factor;
+    let old_liquidation_factor = pool.liquidation_factor;
+
+    pool.collateral_factor = collateral_factor;
+    pool.liquidation_factor = liquidation_factor;
+
+    emit!(PoolParamsUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        old_collateral_factor,
+        new_collateral_factor: collateral_factor,
+        old_liquidation_factor,
+        new_liquidation_factor: liquidation_factor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Update the liquidation settings of a pool (risk admin only).
+/// `protocol_liquidation_share` is the part of the liquidation bonus, in bps,
+/// that goes to the pool's collateral reserve instead of the liquidator.
+pub fn update_pool_liquidation_params(
+    ctx: Context<UpdatePool>,
+    liquidation_penalty_bps: u64,
+    close_factor_bps: u64,
+    debt_dust: u64,
+    protocol_liquidation_share: u64,
+) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    require!(
+        liquidation_penalty_bps <= MAX_LIQUIDATION_PENALTY_BPS,
+        StableError::InvalidParameter
+    );
+    require!(
+        close_factor_bps > 0 && close_factor_bps <= BASIS_POINTS_DIVISOR,
+        StableError::InvalidParameter
+    );
+    require!(
+        protocol_liquidation_share <= BASIS_POINTS_DIVISOR,
+        StableError::InvalidParameter
+    );
+
+    let pool = &mut ctx.accounts.pool;
+
+    let old_liquidation_penalty_bps = pool.liquidation_penalty_bps;
+    let old_close_factor_bps = pool.close_factor_bps;
+    let old_debt_dust = pool.debt_dust;
+    let old_protocol_liquidation_share = pool.protocol_liquidation_share;
+
+    pool.liquidation_penalty_bps = liquidation_penalty_bps;
+    pool.close_factor_bps = close_factor_bps;
+    pool.debt_dust = debt_dust;
+    pool.protocol_liquidation_share = protocol_liquidation_share;
+
+    emit!(PoolLiquidationParamsUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        old_liquidation_penalty_bps,
+        new_liquidation_penalty_bps: liquidation_penalty_bps,
+        old_close_factor_bps,
+        new_close_factor_bps: close_factor_bps,
+        old_debt_dust,
+        new_debt_dust: debt_dust,
+        old_protocol_liquidation_share,
+        new_protocol_liquidation_share: protocol_liquidation_share,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Replace the Dutch auction price curve of a pool (risk admin only)
+pub fn update_pool_auction_config(
+    ctx: Context<UpdatePool>,
+    auction_config: AuctionConfig,
+) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    validate_auction_config(&auction_config)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.auction = auction_config;
+
+    emit!(PoolAuctionConfigUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        curve: auction_config.curve,
+        buf_bps: auction_config.buf_bps,
+        duration: auction_config.duration,
+        step: auction_config.step,
+        cut_bps: auction_config.cut_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Set the share of redeemed collateral kept by the pool reserve (risk admin only)
+pub fn update_pool_redemption_fee(ctx: Context<UpdatePool>, redemption_fee_bps: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    require!(
+        redemption_fee_bps <= MAX_REDEMPTION_FEE_BPS,
+        StableError::InvalidParameter
+    );
+
+    let pool = &mut ctx.accounts.pool;
+    let old_redemption_fee_bps = pool.redemption_fee_bps;
+    pool.redemption_fee_bps = redemption_fee_bps;
+
+    emit!(PoolRedemptionFeeUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        old_redemption_fee_bps,
+        new_redemption_fee_bps: redemption_fee_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Activate or retire a collateral pool.
+/// An inactive pool blocks deposits and mints; repay, withdraw and liquidate keep working.
+/// The risk admin can switch either way, the guardian can only deactivate.
+pub fn set_pool_active(ctx: Context<UpdatePool>, is_active: bool) -> Result<()> {
+    require_pool_status_authority(&ctx.accounts.global_state, &ctx.accounts.authority, !is_active)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.is_active = is_active;
+
+    emit!(PoolStatusUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        is_active: pool.is_active,
+        reduce_only: pool.reduce_only,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Toggle reduce-only mode on a collateral pool.
+/// Deposits and mints are blocked, positions can still be repaid, withdrawn and liquidated.
+/// The risk admin can switch either way, the guardian can only enable it.
+pub fn set_pool_reduce_only(ctx: Context<UpdatePool>, reduce_only: bool) -> Result<()> {
+    require_pool_status_authority(&ctx.accounts.global_state, &ctx.accounts.authority, reduce_only)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.reduce_only = reduce_only;
+
+    emit!(PoolStatusUpdated {
+        admin: ctx.accounts.authority.key(),
+        pool: pool.key(),
+        is_active: pool.is_active,
+        reduce_only: pool.reduce_only,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Close a retired pool that no longer holds shares, debt or stability pool deposits (admin only).
+/// Collateral left in the pool and stability pool accounts is swept into the pool reserve and
+/// stability pool WUSD into the treasury. The pool is removed from the registry and the pool,
+/// its sorted vault index and its stability pool are closed, their rent going back to the admin.
+/// The reserve is created if the pool never had one and stays open until it is emptied with
+/// withdraw_pool_reserve.
+pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    require!(
+        global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    // settlement relies on the registry staying as it was at shutdown
+    require!(!global_state.is_shutdown, StableError::SystemShutdown);
+
+    let pool = &ctx.accounts.pool;
+    require!(!pool.is_active, StableError::PoolStillActive);
+    require!(
+        pool.total_shares == 0
+            && pool.total_normalized_debt == 0
+            && pool.auction_collateral == 0,
+        StableError::PoolNotEmpty
+    );
+
+    // every deposit must be fully withdrawn, which also pays out its collateral gain
+    let stability_pool = load_stability_pool(&ctx.accounts.stability_pool)?;
+    if let Some(stability_pool) = &stability_pool {
+        require!(
+            stability_pool.total_deposits == 0 && stability_pool.open_deposits == 0,
+            StableError::PoolNotEmpty
+        );
+    }
+
+    let pool_key = pool.key();
+    let pool_registry = &mut ctx.accounts.pool_registry;
+    pool_registry.pools.retain(|key| *key != pool_key);
+    global_state.pool_count = global_state.pool_count
+        .checked_sub(1)
+        .ok_or(StableError::Overflow)?;
+
+    let seeds = &[SEED_POOL, pool.mint.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+
+    let mut collateral_swept = ctx.accounts.pool_collateral_account.amount;
+    if collateral_swept > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.pool_reserve.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signer,
+            ),
+            collateral_swept,
+        )?;
+    }
+
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.pool_collateral_account.to_account_info(),
+            destination: ctx.accounts.admin.to_account_info(),
+            authority: pool.to_account_info(),
+        },
+        signer,
+    ))?;
+
+    let mut stable_swept = 0;
+    if let Some(stability_pool) = stability_pool {
+        let stability_seeds = &[SEED_STABILITY_POOL, pool_key.as_ref(), &[stability_pool.bump]];
+        let stability_signer = &[&stability_seeds[..]];
+
+        // rounding dust left behind by the product-sum bookkeeping
+        let collateral_dust = sweep_stability_pool_account(
+            &ctx,
+            &ctx.accounts.stability_pool_collateral_account,
+            &ctx.accounts.pool_reserve.to_account_info(),
+            stability_signer,
+        )?;
+        stable_swept = sweep_stability_pool_account(
+            &ctx,
+            &ctx.accounts.stability_pool_stable_account,
+            &ctx.accounts.treasury.to_account_info(),
+            stability_signer,
+        )?;
+        collateral_swept = collateral_swept
+            .checked_add(collateral_dust)
+            .ok_or(StableError::Overflow)?;
+
+        close_program_account(&ctx.accounts.stability_pool, &ctx.accounts.admin)?;
+    }
+
+    if !ctx.accounts.sorted_vaults.data_is_empty() {
+        close_program_account(&ctx.accounts.sorted_vaults, &ctx.accounts.admin)?;
+    }
+
+    emit!(PoolClosed {
+        admin: ctx.accounts.admin.key(),
+        pool: pool_key,
+        collateral_mint: pool.mint,
+        collateral_swept,
+        stable_swept,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Move a stability pool token account's balance to `destination` and close it.
+/// Returns the amount moved.
+fn sweep_stability_pool_account<'info>(
+    ctx: &Context<ClosePool<'info>>,
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<u64> {
+    let amount = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?.amount;
+    if amount > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: account.clone(),
+                    to: destination.clone(),
+                    authority: ctx.accounts.stability_pool.clone(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    }
+
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: account.clone(),
+            destination: ctx.accounts.admin.to_account_info(),
+            authority: ctx.accounts.stability_pool.clone(),
+        },
+        signer,
+    ))?;
+
+    Ok(amount)
+}
+
+/// Close an account owned by this program, returning its rent to `destination`
+pub(crate) fn close_program_account<'info>(info: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = info.lamports();
+    **info.try_borrow_mut_lamports()? = 0;
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(StableError::Overflow)?;
+
+    info.assign(&anchor_lang::system_program::ID);
+    info.resize(0)?;
+    Ok(())
+}
+
+/// Risk admin may change pool status freely, the guardian only towards de-risking
+fn require_pool_status_authority(
+    global_state: &GlobalState,
+    authority: &Signer,
+    is_derisking: bool,
+) -> Result<()> {
+    let authority = authority.key();
+    require!(
+        authority == global_state.risk_admin
+            || (is_derisking && authority == global_state.guardian),
+        StableError::Unauthorized
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = treasury_authority,
+    )]
+    pub pool_reserve: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: sorted vault index PDA of the pool, may not exist
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump
+    )]
+    pub sorted_vaults: AccountInfo<'info>,
+
+    /// CHECK: stability pool PDA of the pool, may not exist
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump
+    )]
+    pub stability_pool: AccountInfo<'info>,
+
+    /// CHECK: stability pool WUSD account, only read when the stability pool exists
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump
+    )]
+    pub stability_pool_stable_account: AccountInfo<'info>,
+
+    /// CHECK: stability pool collateral account, only read when the stability pool exists
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump
+    )]
+    pub stability_pool_collateral_account: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,586 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+
+/// Register a stable collateral with the peg stability module (admin only).
+/// The collateral must have the same decimals as WUSD so swaps are 1:1 in base units.
+pub fn initialize_psm(
+    ctx: Context<InitializePsm>,
+    tin_bps: u64,
+    tout_bps: u64,
+    debt_ceiling: u64,
+) -> Result<()> {
+    let global_state = &ctx.accounts.global_state;
+    require!(
+        global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(!global_state.is_shutdown, StableError::SystemShutdown);
+    require!(
+        ctx.accounts.collateral_mint.decimals == ctx.accounts.stablecoin_mint.decimals,
+        StableError::InvalidParameter
+    );
+    validate_psm_params(tin_bps, tout_bps)?;
+
+    let psm = &mut ctx.accounts.psm;
+    psm.mint = ctx.accounts.collateral_mint.key();
+    psm.tin_bps = tin_bps;
+    psm.tout_bps = tout_bps;
+    psm.debt_ceiling = debt_ceiling;
+    psm.debt = 0;
+    psm.bump = ctx.bumps.psm;
+
+    emit!(PsmInitialized {
+        admin: ctx.accounts.admin.key(),
+        psm: psm.key(),
+        mint: psm.mint,
+        tin_bps,
+        tout_bps,
+        debt_ceiling,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Update the PSM fees and debt ceiling (risk admin only)
+pub fn update_psm_params(
+    ctx: Context<UpdatePsmParams>,
+    tin_bps: u64,
+    tout_bps: u64,
+    debt_ceiling: u64,
+) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    validate_psm_params(tin_bps, tout_bps)?;
+
+    let psm = &mut ctx.accounts.psm;
+
+    let old_tin_bps = psm.tin_bps;
+    let old_tout_bps = psm.tout_bps;
+    let old_debt_ceiling = psm.debt_ceiling;
+
+    psm.tin_bps = tin_bps;
+    psm.tout_bps = tout_bps;
+    psm.debt_ceiling = debt_ceiling;
+
+    emit!(PsmParamsUpdated {
+        admin: ctx.accounts.authority.key(),
+        psm: psm.key(),
+        old_tin_bps,
+        new_tin_bps: tin_bps,
+        old_tout_bps,
+        new_tout_bps: tout_bps,
+        old_debt_ceiling,
+        new_debt_ceiling: debt_ceiling,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Swap `amount` stable collateral for WUSD.
+/// `amount` WUSD is minted against the reserve: the `tin` fee goes to the treasury, the rest to the user.
+pub fn swap_to_stable(ctx: Context<SwapToStable>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_PSM),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let psm = &mut ctx.accounts.psm;
+    let global_state = &mut ctx.accounts.global_state;
+
+    let new_psm_debt = psm.debt
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+    require!(new_psm_debt <= psm.debt_ceiling, StableError::PsmDebtCeilingReached);
+
+    let new_total_debt = global_state.total_debt
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+    require!(
+        new_total_debt <= global_state.debt_ceiling,
+        StableError::DebtCeilingReached
+    );
+
+    let fee = psm_fee(amount, psm.tin_bps)?;
+    let stable_out = amount - fee;
+
+    psm.debt = new_psm_debt;
+    global_state.total_debt = new_total_debt;
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(fee)
+        .ok_or(StableError::Overflow)?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_collateral_account.to_account_info(),
+                to: ctx.accounts.psm_reserve.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let seeds = &[b"mint_authority".as_ref(), &[ctx.bumps.mint_authority]];
+    let signer = &[&seeds[..]];
+
+    if stable_out > 0 {
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.user_stable_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                signer,
+            ),
+            stable_out,
+        )?;
+    }
+
+    if fee > 0 {
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                signer,
+            ),
+            fee,
+        )?;
+    }
+
+    emit!(PsmSwapEvent {
+        user: ctx.accounts.user.key(),
+        psm: ctx.accounts.psm.key(),
+        to_stable: true,
+        collateral_amount: amount,
+        stable_amount: stable_out,
+        fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Swap WUSD for `amount` stable collateral from the reserve.
+/// The user burns `amount` WUSD and pays the `tout` fee on top to the treasury.
+/// Stays open after shutdown, without the fee, until settlement is finalized: from then on
+/// all WUSD is redeemed pro rata from the settled collateral.
+pub fn swap_from_stable(ctx: Context<SwapFromStable>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_PSM),
+        StableError::OperationPaused
+    );
+    require!(
+        !ctx.accounts.global_state.settlement_finalized,
+        StableError::SettlementAlreadyFinalized
+    );
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let psm = &mut ctx.accounts.psm;
+    let global_state = &mut ctx.accounts.global_state;
+
+    require!(
+        amount <= psm.debt && amount <= ctx.accounts.psm_reserve.amount,
+        StableError::InsufficientPsmReserves
+    );
+
+    // the treasury is burned at settlement, fees paid after shutdown would be stranded there
+    let fee = if global_state.is_shutdown { 0 } else { psm_fee(amount, psm.tout_bps)? };
+
+    psm.debt -= amount;
+    global_state.total_debt = global_state.total_debt.saturating_sub(amount);
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(fee)
+        .ok_or(StableError::Overflow)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.user_stable_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    if fee > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    let seeds = &[SEED_PSM, ctx.accounts.psm.mint.as_ref(), &[ctx.accounts.psm.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.psm_reserve.to_account_info(),
+                to: ctx.accounts.user_collateral_account.to_account_info(),
+                authority: ctx.accounts.psm.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    emit!(PsmSwapEvent {
+        user: ctx.accounts.user.key(),
+        psm: ctx.accounts.psm.key(),
+        to_stable: false,
+        collateral_amount: amount,
+        stable_amount: amount,
+        fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn validate_psm_params(tin_bps: u64, tout_bps: u64) -> Result<()> {
+    require!(tin_bps <= MAX_PSM_FEE_BPS, StableError::InvalidParameter);
+    require!(tout_bps <= MAX_PSM_FEE_BPS, StableError::InvalidParameter);
+    Ok(())
+}
+
+fn psm_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    Ok(((amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64)
+}
+
+#[derive(Accounts)]
+pub struct InitializePsm<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Psm::INIT_SPACE,
+        seeds = [SEED_PSM, collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub psm: Account<'info, Psm>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_PSM_RESERVE, psm.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = psm,
+    )]
+    pub psm_reserve: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePsmParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PSM, psm.mint.as_ref()],
+        bump = psm.bump,
+    )]
+    pub psm: Account<'info, Psm>,
+}
+
+#[derive(Accounts)]
+pub struct SwapToStable<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PSM, psm.mint.as_ref()],
+        bump = psm.bump,
+    )]
+    pub psm: Box<Account<'info, Psm>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PSM_RESERVE, psm.key().as_ref()],
+        bump,
+    )]
+    pub psm_reserve: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == psm.mint,
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA for minting stablecoins
+    #[account(
+        seeds = [b"mint_authority"],
+        bump
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SwapFromStable<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PSM, psm.mint.as_ref()],
+        bump = psm.bump,
+    )]
+    pub psm: Box<Account<'info, Psm>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PSM_RESERVE, psm.key().as_ref()],
+        bump,
+    )]
+    pub psm_reserve: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == psm.mint,
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, assert_error, pda, run_instruction, TestAccount};
+
+    const NOW: i64 = 1_700_000_000;
+
+    struct PsmFixture {
+        global_state: GlobalState,
+        psm: Psm,
+        reserve: u64,
+    }
+
+    impl PsmFixture {
+        /// A PSM charging 0.1% in and 0.2% out
+        fn new(debt_ceiling: u64) -> Self {
+            Self {
+                global_state: test_utils::global_state(Pubkey::new_unique(), Pubkey::new_unique()),
+                psm: test_utils::psm(Pubkey::new_unique(), 10, 20, debt_ceiling),
+                reserve: 0,
+            }
+        }
+
+        fn accounts(&self, user: Pubkey, collateral: u64, stable: u64) -> Vec<TestAccount> {
+            let psm_key = pda(&[SEED_PSM, self.psm.mint.as_ref()]);
+            let stablecoin_mint = self.global_state.stablecoin_mint;
+            vec![
+                TestAccount::signer(user),
+                TestAccount::state(pda(&[SEED_GLOBAL]), &self.global_state),
+                TestAccount::mint(stablecoin_mint, pda(&[b"mint_authority"]), 0),
+                TestAccount::state(psm_key, &self.psm),
+                TestAccount::token(pda(&[SEED_PSM_RESERVE, psm_key.as_ref()]), self.psm.mint, psm_key, self.reserve),
+                TestAccount::token(Pubkey::new_unique(), self.psm.mint, user, collateral),
+                TestAccount::token(Pubkey::new_unique(), stablecoin_mint, user, stable),
+                TestAccount::token(pda(&[SEED_TREASURY]), stablecoin_mint, pda(&[SEED_TREASURY_AUTHORITY]), 0),
+            ]
+        }
+
+        fn load(&mut self, accounts: &[TestAccount]) {
+            self.global_state = accounts[1].load();
+            self.psm = accounts[3].load();
+        }
+
+        fn swap_in(&mut self, amount: u64) -> Result<()> {
+            let mut accounts = self.accounts(Pubkey::new_unique(), amount, 0);
+            accounts.push(TestAccount::new(pda(&[b"mint_authority"]), anchor_lang::system_program::ID, Vec::new()).read_only());
+            accounts.push(TestAccount::program(anchor_spl::token::ID));
+
+            run_instruction!(SwapToStable, accounts, |ctx| swap_to_stable(ctx, amount))?;
+            self.load(&accounts);
+            // token transfers don't run off-chain
+            self.reserve += amount;
+            Ok(())
+        }
+
+        fn swap_out(&mut self, amount: u64) -> Result<()> {
+            let mut accounts = self.accounts(Pubkey::new_unique(), 0, 2 * amount);
+            accounts.push(TestAccount::program(anchor_spl::token::ID));
+
+            run_instruction!(SwapFromStable, accounts, |ctx| swap_from_stable(ctx, amount))?;
+            self.load(&accounts);
+            self.reserve -= amount;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn swaps_in_and_out_track_the_psm_debt() {
+        test_utils::set_time(NOW);
+        let mut fixture = PsmFixture::new(1_000_000);
+
+        fixture.swap_in(400_000).unwrap();
+        assert_eq!(fixture.psm.debt, 400_000);
+        assert_eq!(fixture.global_state.total_debt, 400_000);
+        assert_eq!(fixture.global_state.total_fees_collected, 400);
+
+        fixture.swap_out(150_000).unwrap();
+        assert_eq!(fixture.psm.debt, 250_000);
+        assert_eq!(fixture.global_state.total_debt, 250_000);
+        assert_eq!(fixture.global_state.total_fees_collected, 400 + 300);
+
+        assert_error(fixture.swap_out(250_001), StableError::InsufficientPsmReserves);
+        fixture.swap_out(250_000).unwrap();
+        assert_eq!(fixture.psm.debt, 0);
+        assert_eq!(fixture.global_state.total_debt, 0);
+    }
+
+    #[test]
+    fn swaps_in_stop_at_the_debt_ceilings() {
+        test_utils::set_time(NOW);
+        let mut fixture = PsmFixture::new(1_000_000);
+
+        fixture.swap_in(999_999).unwrap();
+        assert_error(fixture.swap_in(2), StableError::PsmDebtCeilingReached);
+        fixture.swap_in(1).unwrap();
+        assert_eq!(fixture.psm.debt, 1_000_000);
+
+        // the global ceiling covers PSM debt too
+        let mut fixture = PsmFixture::new(1_000_000);
+        fixture.global_state.total_debt = fixture.global_state.debt_ceiling - 500;
+        assert_error(fixture.swap_in(501), StableError::DebtCeilingReached);
+        fixture.swap_in(500).unwrap();
+    }
+
+    #[test]
+    fn swaps_out_are_free_after_shutdown_and_closed_at_finalization() {
+        test_utils::set_time(NOW);
+        let mut fixture = PsmFixture::new(1_000_000);
+        fixture.swap_in(100_000).unwrap();
+
+        fixture.global_state.is_shutdown = true;
+        assert_error(fixture.swap_in(1), StableError::SystemShutdown);
+
+        let fees = fixture.global_state.total_fees_collected;
+        fixture.swap_out(40_000).unwrap();
+        assert_eq!(fixture.global_state.total_fees_collected, fees);
+
+        fixture.global_state.settlement_finalized = true;
+        assert_error(fixture.swap_out(1), StableError::SettlementAlreadyFinalized);
+    }
+
+    #[test]
+    fn swaps_respect_the_pause() {
+        test_utils::set_time(NOW);
+        let mut fixture = PsmFixture::new(1_000_000);
+        fixture.swap_in(100).unwrap();
+
+        fixture.global_state.paused_operations = PAUSE_PSM;
+        assert_error(fixture.swap_in(100), StableError::OperationPaused);
+        assert_error(fixture.swap_out(100), StableError::OperationPaused);
+    }
+}
@@ -0,0 +1,365 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Create the sorted vault index of a pool created before `initialize_pool` set it up (admin only)
+pub fn initialize_sorted_vaults(ctx: Context<InitializeSortedVaults>) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+
+    let mut sorted_vaults = ctx.accounts.sorted_vaults.load_init()?;
+    sorted_vaults.pool = ctx.accounts.pool.key();
+    sorted_vaults.len = 0;
+    sorted_vaults.bump = ctx.bumps.sorted_vaults;
+
+    Ok(())
+}
+
+/// Put a vault back at its place in the pool's index (anyone can call).
+/// Needed for vaults that dropped off a full index or predate it.
+pub fn sync_sorted_vault(ctx: Context<SyncSortedVault>) -> Result<()> {
+    update_sorted_vaults(
+        &ctx.accounts.sorted_vaults,
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.user_vault,
+    )
+}
+
+/// Burn up to `amount` WUSD for $1 of collateral per WUSD, minus the pool's redemption fee.
+/// Debt is taken from the riskiest vaults first: `remaining_accounts` must be the writable
+/// vaults at the front of the pool's sorted index, in index order.
+/// Vaults that are already liquidatable are skipped. Fails if the fee is above `max_fee_bps`.
+/// The interest part of the redeemed debt goes to the treasury like a repayment, the rest is burned.
+pub fn redeem<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Redeem<'info>>,
+    amount: u64,
+    max_fee_bps: u64,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_REDEEM),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+    require!(
+        ctx.accounts.pool.redemption_fee_bps <= max_fee_bps,
+        StableError::RedemptionFeeTooHigh
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+
+    let oracle_price = load_collateral_price(&ctx.accounts.price_feed, &ctx.accounts.pool)?;
+    let payout_price = oracle_price.max_price()?;
+    let collateral_decimals = ctx.accounts.collateral_mint.decimals;
+
+    // the vaults passed in have to be the front of the index
+    let targets: Vec<Pubkey> = {
+        let sorted_vaults = ctx.accounts.sorted_vaults.load()?;
+        require!(
+            ctx.remaining_accounts.len() <= sorted_vaults.len as usize,
+            StableError::InvalidParameter
+        );
+        sorted_vaults.entries()[..ctx.remaining_accounts.len()]
+            .iter()
+            .map(|entry| entry.vault)
+            .collect()
+    };
+
+    let pool = &mut ctx.accounts.pool;
+    let global_state = &mut ctx.accounts.global_state;
+    let mut remaining = amount;
+    let mut total_collateral_out: u64 = 0;
+    let mut total_interest_paid: u64 = 0;
+
+    for (vault_info, target) in ctx.remaining_accounts.iter().zip(targets.iter()) {
+        if remaining == 0 {
+            break;
+        }
+        require_keys_eq!(vault_info.key(), *target, StableError::InvalidParameter);
+
+        let mut user_vault = Account::<UserVault>::try_from(vault_info)?;
+        require_keys_eq!(user_vault.pool, pool.key(), StableError::InvalidPool);
+
+        let owed = sync_vault_debt(&mut user_vault, pool)?;
+        let vault_collateral = calculate_amount_from_shares(
+            user_vault.collateral_shares,
+            pool.total_collateral,
+            pool.total_shares,
+        )?;
+
+        // liquidatable vaults are left for the liquidators
+        let collateral_value = calculate_collateral_value(vault_collateral, oracle_price.price, collateral_decimals)?;
+        if calculate_collateral_ratio_bps(collateral_value, owed)? < pool.liquidation_factor {
+            continue;
+        }
+
+        let debt_redeemed = remaining.min(owed);
+        // don't leave debt below the pool's dust threshold behind
+        let debt_left = owed - debt_redeemed;
+        if debt_left > 0 && debt_left < pool.debt_dust {
+            break;
+        }
+
+        let collateral_out = calculate_collateral_amount(debt_redeemed, payout_price, collateral_decimals)?
+            .min(vault_collateral);
+        let shares_out = ((collateral_out as u128)
+            .checked_mul(pool.total_shares as u128)
+            .ok_or(StableError::Overflow)?
+            .div_ceil((pool.total_collateral as u128).max(1)) as u64)
+            .min(user_vault.collateral_shares);
+
+        user_vault.collateral_shares -= shares_out;
+        pool.total_shares = pool.total_shares
+            .checked_sub(shares_out)
+            .ok_or(StableError::Overflow)?;
+        pool.total_collateral = pool.total_collateral
+            .checked_sub(collateral_out)
+            .ok_or(StableError::Overflow)?;
+
+        let (_, interest_paid) = repay_vault_debt(&mut user_vault, pool, global_state, debt_redeemed)?;
+        user_vault.last_update = current_time;
+
+        update_sorted_vaults(&ctx.accounts.sorted_vaults, target, &user_vault)?;
+        user_vault.exit(&crate::ID)?;
+
+        remaining -= debt_redeemed;
+        total_collateral_out = total_collateral_out
+            .checked_add(collateral_out)
+            .ok_or(StableError::Overflow)?;
+        total_interest_paid = total_interest_paid
+            .checked_add(interest_paid)
+            .ok_or(StableError::Overflow)?;
+
+        emit!(VaultRedeemed {
+            vault: *target,
+            pool: pool.key(),
+            debt_redeemed,
+            collateral_redeemed: collateral_out,
+            timestamp: current_time,
+        });
+    }
+
+    let stable_redeemed = amount - remaining;
+    require!(stable_redeemed > 0, StableError::NothingToRedeem);
+
+    let fee = ((total_collateral_out as u128)
+        .checked_mul(pool.redemption_fee_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+    let collateral_to_redeemer = total_collateral_out - fee;
+
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(total_interest_paid)
+        .ok_or(StableError::Overflow)?;
+
+    // interest is protocol revenue and goes to the treasury, the rest is burned
+    if total_interest_paid > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.redeemer_stable_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.redeemer.to_account_info(),
+                },
+            ),
+            total_interest_paid,
+        )?;
+    }
+
+    let burn_amount = stable_redeemed
+        .checked_sub(total_interest_paid)
+        .ok_or(StableError::Overflow)?;
+
+    if burn_amount > 0 {
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.redeemer_stable_account.to_account_info(),
+                    authority: ctx.accounts.redeemer.to_account_info(),
+                },
+            ),
+            burn_amount,
+        )?;
+    }
+
+    let seeds = &[SEED_POOL, ctx.accounts.pool.mint.as_ref(), &[ctx.accounts.pool.bump]];
+    let signer = &[&seeds[..]];
+
+    if collateral_to_redeemer > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.redeemer_collateral_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer,
+            ),
+            collateral_to_redeemer,
+        )?;
+    }
+
+    if fee > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.pool_reserve.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer,
+            ),
+            fee,
+        )?;
+    }
+
+    emit!(RedeemEvent {
+        redeemer: ctx.accounts.redeemer.key(),
+        pool: ctx.accounts.pool.key(),
+        stable_redeemed,
+        collateral_received: collateral_to_redeemer,
+        fee,
+        interest_paid: total_interest_paid,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeSortedVaults<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<SortedVaults>(),
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSortedVault<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, user_vault.pool.as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    pub user_vault: Account<'info, UserVault>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    pub redeemer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+        constraint = pool_registry.pools.contains(&pool.key()) @ StableError::InvalidPool
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidPool
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = redeemer_stable_account.owner == redeemer.key(),
+        constraint = redeemer_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub redeemer_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = redeemer_collateral_account.owner == redeemer.key(),
+        constraint = redeemer_collateral_account.mint == collateral_mint.key(),
+    )]
+    pub redeemer_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = pool,
+    )]
+    pub pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_reserve: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -3,9 +3,15 @@ use crate::events::*;
 use crate::constants::*;
 use crate::states::*;
 use crate::errors::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Burn};
+use crate::utils::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint, Burn, Transfer};
 
 pub fn repay(ctx: Context<Repay>, repay_amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_REPAY),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
     require!(repay_amount > 0, StableError::InvalidParameter);
 
     let pool_key  = ctx.accounts.pool.key();
@@ -15,101 +21,71 @@ pub fn repay(ctx: Context<Repay>, repay_amount: u64) -> Result<()> {
     let user_vault = &mut ctx.accounts.user_vault;
     let global_state = &mut ctx.accounts.global_state;
 
-    require!(pool.is_active, StableError::PoolNotActive);
     require!(
         user_vault.owner == user_key && user_vault.pool == pool_key,
         StableError::InvalidPool
     );
 
     let current_time = Clock::get()?.unix_timestamp;
-    let time_elapsed = current_time - user_vault.last_update;
-
-    let new_interest = if time_elapsed > 0 && user_vault.debt_amount > 0 {
-        let apr_bps = global_state.stability_fee;
-        let interest_rate = (apr_bps as f64) / (BASIS_POINTS_DIVISOR as f64);
-        let per_second_rate = interest_rate / 31536000.0;
-
-        ((user_vault.debt_amount as f64) * per_second_rate * (time_elapsed as f64)) as u64
-    } else {
-        0
-    };
-
-    // calculate total owed 
-    let total_owed = user_vault.debt_amount
-        .checked_add(user_vault.accrued_interest)
-        .ok_or(StableError::Overflow)?
-        .checked_add(new_interest)
-        .ok_or(StableError::Overflow)?;
+    accrue_pool_interest(pool, global_state, current_time)?;
+
+    // calculate total owed at the pool's current rate index
+    let total_owed = sync_vault_debt(user_vault, pool)?;
 
     require!(
         repay_amount <= total_owed,
         StableError::RepayAmountExceedsDebt
     );
-
-    // burn stablecoins from user
-    let burn_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Burn {
-            mint: ctx.accounts.stablecoin_mint.to_account_info(),
-            from: ctx.accounts.user_stable_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        },
-    );
-
-    token::burn(burn_ctx, repay_amount)?;
-
-    // track whats being paid
-    let mut remaining_repay = repay_amount;
-    let mut interest_paid = 0u64;
-    let mut principal_paid = 0u64;
-
-    // pay off the accrued interest
-    if remaining_repay > 0 && user_vault.accrued_interest > 0 {
-        let pay_accrued_interest = user_vault.accrued_interest.min(remaining_repay);
-        
-        user_vault.accrued_interest = user_vault.accrued_interest
-            .checked_sub(pay_accrued_interest)
-            .ok_or(StableError::Overflow)?;
-
-        remaining_repay = remaining_repay
-            .checked_sub(pay_accrued_interest)
-            .ok_or(StableError::Overflow)?;
-
-        interest_paid = interest_paid
-            .checked_add(pay_accrued_interest)
-            .ok_or(StableError::Overflow)?;
-    }
-    
-    // pay off the new interest
-    if remaining_repay > 0 && new_interest > 0 {
-        let pay_new_interest = new_interest.min(remaining_repay);
-        
-        // new interest hasn't been added to accrued yet, so just track payment
-        remaining_repay = remaining_repay
-            .checked_sub(pay_new_interest)
-            .ok_or(StableError::Overflow)?;
+    // either close the debt or leave at least the pool's dust amount
+    let debt_left = total_owed - repay_amount;
+    require!(debt_left == 0 || debt_left >= pool.debt_dust, StableError::DebtBelowDust);
+
+    // pay off interest first, then principal
+    let (principal_paid, interest_paid) = repay_vault_debt(
+        user_vault,
+        pool,
+        global_state,
+        repay_amount,
+    )?;
+
+    global_state.total_fees_collected = global_state.total_fees_collected
+        .checked_add(interest_paid)
+        .ok_or(StableError::Overflow)?;
     
-        interest_paid = interest_paid
-            .checked_add(pay_new_interest)
-            .ok_or(StableError::Overflow)?;
-    }
+    user_vault.last_update = current_time;
 
-    // pay off principal debt
-    if remaining_repay > 0 && user_vault.debt_amount > 0 {
-        let pay_principal = user_vault.debt_amount.min(remaining_repay);
-        
-        user_vault.debt_amount = user_vault.debt_amount
-            .checked_sub(pay_principal)
-            .ok_or(StableError::Overflow)?;
+    // interest is protocol revenue and goes to the treasury, the rest is burned
+    if interest_paid > 0 {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_stable_account.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+
+        token::transfer(transfer_ctx, interest_paid)?;
+    }
 
-        principal_paid = pay_principal;
+    let burn_amount = repay_amount
+        .checked_sub(interest_paid)
+        .ok_or(StableError::Overflow)?;
 
-        global_state.total_debt = global_state.total_debt
-            .checked_sub(pay_principal)
-            .ok_or(StableError::Overflow)?;
+    if burn_amount > 0 {
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.user_stable_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+
+        token::burn(burn_ctx, burn_amount)?;
     }
-    
-    user_vault.last_update = current_time;
+
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
 
     emit!(RepayEvent {
         user: user_key,
@@ -159,11 +135,18 @@ pub struct Repay<'info> {
 
     #[account(
         mut, 
-        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref()],
+        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
         bump
     )]
     pub user_vault: Account<'info, UserVault>,
 
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
     #[account(
         mut,
         constraint = user_stable_account.owner == user.key(),
@@ -171,5 +154,12 @@ pub struct Repay<'info> {
     )]
     pub user_stable_account: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
     pub token_program: Program<'info, Token>,
 }
\ No newline at end of file
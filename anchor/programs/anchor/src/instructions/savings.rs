@@ -0,0 +1,428 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Create the savings module with its sWUSD share mint and WUSD account (admin only)
+pub fn initialize_savings(ctx: Context<InitializeSavings>, savings_rate_bps: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(savings_rate_bps <= MAX_SAVINGS_RATE_BPS, StableError::InvalidParameter);
+
+    let savings = &mut ctx.accounts.savings;
+    savings.share_mint = ctx.accounts.share_mint.key();
+    savings.total_assets = 0;
+    savings.savings_rate_bps = savings_rate_bps;
+    savings.last_accrual = Clock::get()?.unix_timestamp;
+    savings.bump = ctx.bumps.savings;
+
+    Ok(())
+}
+
+/// Change the savings rate (risk admin only). Interest up to now is paid at the old rate.
+pub fn set_savings_rate(ctx: Context<SetSavingsRate>, savings_rate_bps: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.risk_admin == ctx.accounts.authority.key(),
+        StableError::Unauthorized
+    );
+    require!(savings_rate_bps <= MAX_SAVINGS_RATE_BPS, StableError::InvalidParameter);
+
+    let accounts = &mut *ctx.accounts;
+    fund_savings_interest(
+        &mut accounts.savings,
+        &accounts.global_state,
+        &accounts.treasury,
+        &accounts.treasury_authority,
+        ctx.bumps.treasury_authority,
+        &accounts.savings_stable_account,
+        &accounts.token_program,
+    )?;
+
+    let old_savings_rate_bps = accounts.savings.savings_rate_bps;
+    accounts.savings.savings_rate_bps = savings_rate_bps;
+
+    emit!(SavingsRateUpdated {
+        admin: accounts.authority.key(),
+        old_savings_rate_bps,
+        new_savings_rate_bps: savings_rate_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Pay savings interest up to now out of the treasury (anyone can call)
+pub fn accrue_savings_interest(ctx: Context<AccrueSavings>) -> Result<()> {
+    let accounts = &mut *ctx.accounts;
+    fund_savings_interest(
+        &mut accounts.savings,
+        &accounts.global_state,
+        &accounts.treasury,
+        &accounts.treasury_authority,
+        ctx.bumps.treasury_authority,
+        &accounts.savings_stable_account,
+        &accounts.token_program,
+    )
+}
+
+/// Deposit WUSD and receive sWUSD shares at the current exchange rate
+pub fn deposit_savings(ctx: Context<SavingsAction>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_SAVINGS),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let accounts = &mut *ctx.accounts;
+    fund_savings_interest(
+        &mut accounts.savings,
+        &accounts.global_state,
+        &accounts.treasury,
+        &accounts.treasury_authority,
+        ctx.bumps.treasury_authority,
+        &accounts.savings_stable_account,
+        &accounts.token_program,
+    )?;
+
+    let savings = &mut accounts.savings;
+    let shares = calculate_shares_from_amount(amount, accounts.share_mint.supply, savings.total_assets)?;
+    savings.total_assets = savings.total_assets
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_stable_account.to_account_info(),
+                to: accounts.savings_stable_account.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let seeds = &[SEED_SAVINGS, &[savings.bump]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: accounts.share_mint.to_account_info(),
+                to: accounts.user_share_account.to_account_info(),
+                authority: savings.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        shares,
+    )?;
+
+    emit!(SavingsDeposited {
+        user: accounts.user.key(),
+        amount,
+        shares_minted: shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Burn sWUSD shares for their WUSD at the current exchange rate.
+/// Still open after shutdown, interest stops accruing then.
+pub fn redeem_savings(ctx: Context<SavingsAction>, shares: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_SAVINGS),
+        StableError::OperationPaused
+    );
+    require!(shares > 0, StableError::InvalidParameter);
+
+    let accounts = &mut *ctx.accounts;
+    fund_savings_interest(
+        &mut accounts.savings,
+        &accounts.global_state,
+        &accounts.treasury,
+        &accounts.treasury_authority,
+        ctx.bumps.treasury_authority,
+        &accounts.savings_stable_account,
+        &accounts.token_program,
+    )?;
+
+    let savings = &mut accounts.savings;
+    let amount = calculate_amount_from_shares(shares, savings.total_assets, accounts.share_mint.supply)?;
+    savings.total_assets = savings.total_assets
+        .checked_sub(amount)
+        .ok_or(StableError::Overflow)?;
+
+    token::burn(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Burn {
+                mint: accounts.share_mint.to_account_info(),
+                from: accounts.user_share_account.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    if amount > 0 {
+        let seeds = &[SEED_SAVINGS, &[savings.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts.savings_stable_account.to_account_info(),
+                    to: accounts.user_stable_account.to_account_info(),
+                    authority: savings.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+    }
+
+    emit!(SavingsRedeemed {
+        user: accounts.user.key(),
+        shares_burned: shares,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Accrue the savings rate and move the interest from the treasury into the savings account.
+/// Nothing is paid once the system is shut down.
+fn fund_savings_interest<'info>(
+    savings: &mut Account<'info, Savings>,
+    global_state: &GlobalState,
+    treasury: &Account<'info, TokenAccount>,
+    treasury_authority: &AccountInfo<'info>,
+    treasury_authority_bump: u8,
+    savings_stable_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let treasury_balance = if global_state.is_shutdown { 0 } else { treasury.amount };
+    let interest = accrue_savings(savings, treasury_balance, current_time)?;
+    if interest == 0 {
+        return Ok(());
+    }
+
+    let seeds = &[SEED_TREASURY_AUTHORITY, &[treasury_authority_bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: treasury.to_account_info(),
+                to: savings_stable_account.to_account_info(),
+                authority: treasury_authority.clone(),
+            },
+            &[&seeds[..]],
+        ),
+        interest,
+    )?;
+
+    emit!(SavingsAccrued {
+        interest_paid: interest,
+        total_assets: savings.total_assets,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeSavings<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Savings::INIT_SPACE,
+        seeds = [SEED_SAVINGS],
+        bump
+    )]
+    pub savings: Box<Account<'info, Savings>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_SAVINGS_MINT],
+        bump,
+        mint::decimals = stablecoin_mint.decimals,
+        mint::authority = savings,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_SAVINGS_STABLE],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = savings,
+    )]
+    pub savings_stable_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueSavings<'info> {
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS],
+        bump = savings.bump,
+    )]
+    pub savings: Box<Account<'info, Savings>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS_STABLE],
+        bump,
+    )]
+    pub savings_stable_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSavingsRate<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS],
+        bump = savings.bump,
+    )]
+    pub savings: Box<Account<'info, Savings>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS_STABLE],
+        bump,
+    )]
+    pub savings_stable_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SavingsAction<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS],
+        bump = savings.bump,
+    )]
+    pub savings: Box<Account<'info, Savings>>,
+
+    #[account(
+        mut,
+        address = savings.share_mint @ StableError::InvalidParameter,
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_SAVINGS_STABLE],
+        bump,
+    )]
+    pub savings_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == global_state.stablecoin_mint,
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_share_account.owner == user.key(),
+        constraint = user_share_account.mint == share_mint.key(),
+    )]
+    pub user_share_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
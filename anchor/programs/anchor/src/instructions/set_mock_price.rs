@@ -2,10 +2,13 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::Mint;
 use crate::constants::*;
 use crate::states::*;
+use crate::errors::*;
 
 /// Initialize or update a mock price feed for local testing
 /// Each collateral mint has its own price feed
 pub fn set_mock_price(ctx: Context<SetMockPrice>, price: i64, confidence: u64) -> Result<()> {
+    require!(cfg!(feature = "mock-oracle"), StableError::MockOracleDisabled);
+
     let mock_price_feed = &mut ctx.accounts.mock_price_feed;
     
     mock_price_feed.collateral_mint = ctx.accounts.collateral_mint.key();
@@ -24,12 +27,12 @@ pub fn set_mock_price(ctx: Context<SetMockPrice>, price: i64, confidence: u64) -
 #[instruction(price: i64, confidence: u64)]
 pub struct SetMockPrice<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub oracle_admin: Signer<'info>,
 
     #[account(
         seeds = [SEED_GLOBAL],
         bump = global_state.bump,
-        constraint = global_state.admin == admin.key()
+        constraint = global_state.oracle_admin == oracle_admin.key() @ StableError::Unauthorized
     )]
     pub global_state: Account<'info, GlobalState>,
 
@@ -38,7 +41,7 @@ pub struct SetMockPrice<'info> {
 
     #[account(
         init_if_needed,
-        payer = admin,
+        payer = oracle_admin,
         space = 8 + MockPriceFeed::INIT_SPACE,
         seeds = [SEED_MOCK_PRICE, collateral_mint.key().as_ref()],
         bump
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+
+/// Replace the pause bitmask (`PAUSE_*` flags).
+/// The admin can pause and unpause anything, the guardian can only add flags.
+pub fn set_paused(ctx: Context<SetPaused>, paused_operations: u32) -> Result<()> {
+    require!(paused_operations & !PAUSE_ALL == 0, StableError::InvalidParameter);
+
+    let global_state = &mut ctx.accounts.global_state;
+    let authority = ctx.accounts.authority.key();
+    let old_paused_operations = global_state.paused_operations;
+
+    let is_admin = authority == global_state.admin;
+    let is_guardian_pause = authority == global_state.guardian
+        && paused_operations & old_paused_operations == old_paused_operations;
+    require!(is_admin || is_guardian_pause, StableError::Unauthorized);
+
+    global_state.paused_operations = paused_operations;
+
+    emit!(PauseUpdated {
+        authority,
+        old_paused_operations,
+        new_paused_operations: paused_operations,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
@@ -0,0 +1,701 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Start global settlement (admin only).
+/// Deposits, mints, repayments and liquidations stop for good; each pool is then caged
+/// at a final price, vaults are settled against it and WUSD becomes redeemable for collateral.
+pub fn shutdown(ctx: Context<Shutdown>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    require!(
+        global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(!global_state.is_shutdown, StableError::SystemShutdown);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    global_state.is_shutdown = true;
+    global_state.shutdown_time = current_time;
+
+    emit!(SystemShutdown {
+        admin: ctx.accounts.admin.key(),
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Freeze a pool at its final oracle price (admin only, after shutdown).
+/// Interest is accrued one last time and the rate index stops moving.
+pub fn cage_pool(ctx: Context<CagePool>) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(ctx.accounts.global_state.is_shutdown, StableError::SystemNotShutdown);
+    require!(!ctx.accounts.pool.is_caged, StableError::PoolCaged);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    accrue_pool_interest(&mut ctx.accounts.pool, &mut ctx.accounts.global_state, current_time)?;
+
+    let final_price = load_collateral_price(&ctx.accounts.price_feed, &ctx.accounts.pool)?.price;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.is_caged = true;
+    pool.final_price = final_price;
+
+    emit!(PoolCaged {
+        admin: ctx.accounts.admin.key(),
+        pool: pool.key(),
+        final_price,
+        total_debt: pool_debt(pool)?,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Net a vault's debt against its collateral at the pool's final price (anyone can call).
+/// The collateral covering the debt moves into the pool's settlement bucket for WUSD holders,
+/// whatever is left stays in the vault for the owner to withdraw.
+pub fn settle_vault(ctx: Context<SettleVault>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let user_vault = &mut ctx.accounts.user_vault;
+    let global_state = &mut ctx.accounts.global_state;
+
+    require!(pool.is_caged, StableError::PoolNotCaged);
+    require!(user_vault.normalized_debt > 0, StableError::InvalidParameter);
+
+    let owed = sync_vault_debt(user_vault, pool)?;
+
+    let collateral_owed = calculate_collateral_amount(
+        owed,
+        pool.final_price,
+        ctx.accounts.collateral_mint.decimals,
+    )?;
+
+    // round the shares taken up so the vault never keeps collateral backing its debt
+    let shares_owed = if pool.total_collateral == 0 {
+        user_vault.collateral_shares
+    } else {
+        (collateral_owed as u128)
+            .checked_mul(pool.total_shares as u128)
+            .ok_or(StableError::Overflow)?
+            .div_ceil(pool.total_collateral as u128)
+            .min(user_vault.collateral_shares as u128) as u64
+    };
+    let collateral_taken = if shares_owed == 0 {
+        0
+    } else {
+        calculate_amount_from_shares(shares_owed, pool.total_collateral, pool.total_shares)?
+    };
+
+    let value_taken = calculate_collateral_value(
+        collateral_taken,
+        pool.final_price,
+        ctx.accounts.collateral_mint.decimals,
+    )?;
+    let shortfall = owed.saturating_sub(value_taken);
+
+    user_vault.collateral_shares = user_vault.collateral_shares
+        .checked_sub(shares_owed)
+        .ok_or(StableError::Overflow)?;
+
+    pool.total_shares = pool.total_shares
+        .checked_sub(shares_owed)
+        .ok_or(StableError::Overflow)?;
+    pool.total_collateral = pool.total_collateral
+        .checked_sub(collateral_taken)
+        .ok_or(StableError::Overflow)?;
+    pool.settled_collateral = pool.settled_collateral
+        .checked_add(collateral_taken)
+        .ok_or(StableError::Overflow)?;
+    pool.settlement_shortfall = pool.settlement_shortfall
+        .checked_add(shortfall)
+        .ok_or(StableError::Overflow)?;
+
+    // the whole debt is cleared, whether or not the collateral covered it
+    repay_vault_debt(user_vault, pool, global_state, owed)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    user_vault.last_update = current_time;
+
+    emit!(VaultSettled {
+        settler: ctx.accounts.settler.key(),
+        user: user_vault.owner,
+        pool: pool.key(),
+        debt_settled: owed,
+        collateral_taken,
+        shortfall,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Net a cross-margin account's debt against its position in a caged pool at the final price
+/// (anyone can call). Collateral covering the debt moves into the pool's settlement bucket.
+/// Once the account has no positions left, debt still outstanding is written off as the
+/// pool's settlement shortfall.
+pub fn settle_cross_margin(ctx: Context<SettleCrossMargin>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    let account = &mut ctx.accounts.cross_margin_account;
+    let global_state = &mut ctx.accounts.global_state;
+
+    require!(pool.is_caged, StableError::PoolNotCaged);
+    require!(account.normalized_debt > 0, StableError::InvalidParameter);
+
+    let owed = sync_cross_margin_debt(account, global_state)?;
+
+    let (collateral_taken, value_taken) = match account.position(&pool_key).copied() {
+        Some(position) => {
+            let collateral_owed = calculate_collateral_amount(owed, pool.final_price, position.decimals)?;
+
+            // round the shares taken up so the position never keeps collateral backing the debt
+            let shares_owed = if pool.total_collateral == 0 {
+                position.collateral_shares
+            } else {
+                (collateral_owed as u128)
+                    .checked_mul(pool.total_shares as u128)
+                    .ok_or(StableError::Overflow)?
+                    .div_ceil(pool.total_collateral as u128)
+                    .min(position.collateral_shares as u128) as u64
+            };
+            let collateral_taken = if shares_owed == 0 {
+                0
+            } else {
+                calculate_amount_from_shares(shares_owed, pool.total_collateral, pool.total_shares)?
+            };
+
+            account.remove_shares(&pool_key, shares_owed)?;
+            pool.total_shares = pool.total_shares
+                .checked_sub(shares_owed)
+                .ok_or(StableError::Overflow)?;
+            pool.total_collateral = pool.total_collateral
+                .checked_sub(collateral_taken)
+                .ok_or(StableError::Overflow)?;
+            pool.settled_collateral = pool.settled_collateral
+                .checked_add(collateral_taken)
+                .ok_or(StableError::Overflow)?;
+
+            let value_taken = calculate_collateral_value(collateral_taken, pool.final_price, position.decimals)?;
+            (collateral_taken, value_taken.min(owed))
+        }
+        // positions in other pools have to be settled against those pools
+        None => {
+            require!(account.positions.is_empty(), StableError::InvalidPool);
+            (0, 0)
+        }
+    };
+
+    let (debt_settled, shortfall) = if account.positions.is_empty() {
+        (owed, owed - value_taken)
+    } else {
+        (value_taken, 0)
+    };
+    pool.settlement_shortfall = pool.settlement_shortfall
+        .checked_add(shortfall)
+        .ok_or(StableError::Overflow)?;
+
+    repay_cross_margin_debt(account, global_state, debt_settled)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    account.last_update = current_time;
+
+    emit!(CrossMarginSettled {
+        settler: ctx.accounts.settler.key(),
+        user: account.owner,
+        pool: pool_key,
+        debt_settled,
+        collateral_taken,
+        shortfall,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Open WUSD redemptions once every pool is caged and fully settled (anyone can call).
+/// Pools are passed as `remaining_accounts` in registry order. Auctions must have been
+/// yanked and cross-margin debt settled.
+/// Protocol-owned treasury WUSD is burned, then the remaining WUSD supply, PSM-minted WUSD
+/// included, is snapshotted as the denominator of the redemptions. PSM swaps close with it.
+pub fn finalize_settlement<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeSettlement<'info>>,
+) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    require!(global_state.is_shutdown, StableError::SystemNotShutdown);
+    require!(!global_state.settlement_finalized, StableError::SettlementAlreadyFinalized);
+
+    let pool_registry = &ctx.accounts.pool_registry;
+    require!(
+        ctx.remaining_accounts.len() == pool_registry.pools.len(),
+        StableError::InvalidPool
+    );
+
+    for (pool_info, pool_key) in ctx.remaining_accounts.iter().zip(pool_registry.pools.iter()) {
+        require_keys_eq!(pool_info.key(), *pool_key, StableError::InvalidPool);
+
+        let pool = Account::<CollateralPool>::try_from(pool_info)?;
+        require!(pool.is_caged, StableError::PoolNotCaged);
+        require!(pool.total_normalized_debt == 0, StableError::VaultNotSettled);
+        require!(pool.auction_collateral == 0, StableError::AuctionStillRunning);
+    }
+    require!(global_state.cross_normalized_debt == 0, StableError::VaultNotSettled);
+    require!(global_state.auction_debt == 0, StableError::AuctionStillRunning);
+
+    let treasury_burned = ctx.accounts.treasury.amount;
+    let settlement_supply = ctx.accounts.stablecoin_mint.supply
+        .checked_sub(treasury_burned)
+        .ok_or(StableError::Overflow)?;
+    global_state.settlement_finalized = true;
+    global_state.settlement_supply = settlement_supply;
+
+    if treasury_burned > 0 {
+        let treasury_seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&treasury_seeds[..]],
+            ),
+            treasury_burned,
+        )?;
+    }
+
+    emit!(SettlementFinalized {
+        settlement_supply,
+        treasury_burned,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Burn WUSD for a pro-rata share of every pool's settled collateral.
+/// Payouts leave the pools' settled collateral and the burned WUSD leaves the settlement
+/// supply, so every redemption gets its share of what is left and the last one drains the pools.
+/// `remaining_accounts` holds one triple per registered pool, in registry order:
+/// [pool, pool_collateral_account (writable), user_collateral_account (writable)].
+pub fn redeem_settlement<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RedeemSettlement<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let global_state = &mut ctx.accounts.global_state;
+    require!(global_state.settlement_finalized, StableError::SettlementNotFinalized);
+    require!(amount <= global_state.settlement_supply, StableError::InvalidParameter);
+
+    let pool_registry = &ctx.accounts.pool_registry;
+    require!(
+        ctx.remaining_accounts.len() == pool_registry.pools.len() * 3,
+        StableError::InvalidPool
+    );
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                from: ctx.accounts.user_stable_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    for (accounts, pool_key) in ctx.remaining_accounts.chunks(3).zip(pool_registry.pools.iter()) {
+        let pool_info = &accounts[0];
+        require_keys_eq!(pool_info.key(), *pool_key, StableError::InvalidPool);
+        let mut pool = Account::<CollateralPool>::try_from(pool_info)?;
+
+        let pool_collateral_account = Account::<TokenAccount>::try_from(&accounts[1])?;
+        require_keys_eq!(pool_collateral_account.owner, pool.key(), StableError::InvalidPool);
+        require_keys_eq!(pool_collateral_account.mint, pool.mint, StableError::InvalidPool);
+
+        let user_collateral_account = Account::<TokenAccount>::try_from(&accounts[2])?;
+        require_keys_eq!(user_collateral_account.owner, ctx.accounts.user.key(), StableError::Unauthorized);
+        require_keys_eq!(user_collateral_account.mint, pool.mint, StableError::InvalidPool);
+
+        let payout = (amount as u128)
+            .checked_mul(pool.settled_collateral as u128)
+            .ok_or(StableError::Overflow)?
+            .checked_div(global_state.settlement_supply as u128)
+            .ok_or(StableError::Overflow)? as u64;
+        if payout == 0 {
+            continue;
+        }
+
+        pool.settled_collateral -= payout;
+        pool.exit(&crate::ID)?;
+
+        let seeds = &[SEED_POOL, pool.mint.as_ref(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts[1].clone(),
+                    to: accounts[2].clone(),
+                    authority: pool_info.clone(),
+                },
+                signer,
+            ),
+            payout,
+        )?;
+
+        emit!(SettlementRedeemed {
+            user: ctx.accounts.user.key(),
+            pool: pool.key(),
+            stable_burned: amount,
+            collateral_paid: payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    global_state.settlement_supply -= amount;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Shutdown<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct CagePool<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price
+    pub price_feed: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVault<'info> {
+    pub settler: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_VAULT, user_vault.owner.as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.pool == pool.key() @ StableError::InvalidPool
+    )]
+    pub user_vault: Account<'info, UserVault>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCrossMargin<'info> {
+    pub settler: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CROSS_MARGIN, cross_margin_account.owner.as_ref()],
+        bump = cross_margin_account.bump,
+    )]
+    pub cross_margin_account: Box<Account<'info, CrossMarginAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSettlement<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemSettlement<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+
+    #[account(
+        mut,
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == stablecoin_mint.key(),
+    )]
+    pub user_stable_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, assert_error, pda, run_instruction, TestAccount};
+
+    const NOW: i64 = 1_700_000_000;
+
+    struct Settlement {
+        admin: Pubkey,
+        global_state: GlobalState,
+        pools: Vec<CollateralPool>,
+        supply: u64,
+        treasury: u64,
+    }
+
+    impl Settlement {
+        /// Shut down with every pool caged and settled
+        fn new(settled_collateral: &[u64], supply: u64, treasury: u64) -> Self {
+            let admin = Pubkey::new_unique();
+            let mut global_state = test_utils::global_state(admin, Pubkey::new_unique());
+            global_state.is_shutdown = true;
+            let pools = settled_collateral
+                .iter()
+                .map(|&settled_collateral| CollateralPool {
+                    is_caged: true,
+                    final_price: 100_00000000,
+                    settled_collateral,
+                    ..test_utils::collateral_pool(Pubkey::new_unique(), [0; 32])
+                })
+                .collect();
+            Self { admin, global_state, pools, supply, treasury }
+        }
+
+        fn pool_keys(&self) -> Vec<Pubkey> {
+            self.pools.iter().map(|pool| pda(&[SEED_POOL, pool.mint.as_ref()])).collect()
+        }
+
+        fn common_accounts(&self, signer: Pubkey) -> Vec<TestAccount> {
+            vec![
+                TestAccount::signer(signer),
+                TestAccount::state(pda(&[SEED_GLOBAL]), &self.global_state),
+                TestAccount::state(
+                    pda(&[SEED_POOL_REGISTRY]),
+                    &test_utils::pool_registry(self.admin, self.pool_keys()),
+                )
+                .read_only(),
+                TestAccount::mint(self.global_state.stablecoin_mint, pda(&[b"mint_authority"]), self.supply),
+            ]
+        }
+
+        fn finalize(&mut self) -> Result<()> {
+            let treasury_authority = pda(&[SEED_TREASURY_AUTHORITY]);
+            let mut accounts = self.common_accounts(self.admin);
+            accounts.push(TestAccount::new(treasury_authority, anchor_lang::system_program::ID, Vec::new()).read_only());
+            accounts.push(TestAccount::token(
+                pda(&[SEED_TREASURY]),
+                self.global_state.stablecoin_mint,
+                treasury_authority,
+                self.treasury,
+            ));
+            accounts.push(TestAccount::program(anchor_spl::token::ID));
+            for (pool, key) in self.pools.iter().zip(self.pool_keys()) {
+                accounts.push(TestAccount::state(key, pool).read_only());
+            }
+
+            run_instruction!(FinalizeSettlement, accounts, finalize_settlement)?;
+            self.global_state = accounts[1].load();
+            Ok(())
+        }
+
+        /// Redeem `amount` WUSD, returning the collateral paid out of each pool
+        fn redeem(&mut self, amount: u64) -> Result<Vec<u64>> {
+            let user = Pubkey::new_unique();
+            let mut accounts = self.common_accounts(user);
+            accounts.push(TestAccount::token(Pubkey::new_unique(), self.global_state.stablecoin_mint, user, amount));
+            accounts.push(TestAccount::program(anchor_spl::token::ID));
+            for (pool, key) in self.pools.iter().zip(self.pool_keys()) {
+                accounts.push(TestAccount::state(key, pool));
+                accounts.push(TestAccount::token(Pubkey::new_unique(), pool.mint, key, pool.settled_collateral));
+                accounts.push(TestAccount::token(Pubkey::new_unique(), pool.mint, user, 0));
+            }
+
+            run_instruction!(RedeemSettlement, accounts, |ctx| redeem_settlement(ctx, amount))?;
+            self.global_state = accounts[1].load();
+            let first_pool = accounts.len() - 3 * self.pools.len();
+            let payouts = self.pools
+                .iter_mut()
+                .zip(accounts[first_pool..].chunks(3))
+                .map(|(pool, accounts)| {
+                    let settled_collateral = accounts[0].load::<CollateralPool>().settled_collateral;
+                    let payout = pool.settled_collateral - settled_collateral;
+                    pool.settled_collateral = settled_collateral;
+                    payout
+                })
+                .collect();
+            Ok(payouts)
+        }
+    }
+
+    #[test]
+    fn finalize_waits_for_every_pool_to_settle() {
+        test_utils::set_time(NOW);
+
+        let mut settlement = Settlement::new(&[1_000, 2_000], 10_000, 0);
+        settlement.pools[1].is_caged = false;
+        assert_error(settlement.finalize(), StableError::PoolNotCaged);
+
+        let mut settlement = Settlement::new(&[1_000, 2_000], 10_000, 0);
+        settlement.pools[0].total_normalized_debt = 1;
+        assert_error(settlement.finalize(), StableError::VaultNotSettled);
+
+        let mut settlement = Settlement::new(&[1_000, 2_000], 10_000, 0);
+        settlement.pools[1].auction_collateral = 1;
+        assert_error(settlement.finalize(), StableError::AuctionStillRunning);
+
+        let mut settlement = Settlement::new(&[1_000, 2_000], 10_000, 0);
+        settlement.global_state.cross_normalized_debt = 1;
+        assert_error(settlement.finalize(), StableError::VaultNotSettled);
+    }
+
+    #[test]
+    fn finalize_snapshots_the_supply_outside_the_treasury() {
+        test_utils::set_time(NOW);
+        let mut settlement = Settlement::new(&[1_000], 10_000, 1_500);
+
+        assert_error(settlement.redeem(100), StableError::SettlementNotFinalized);
+        settlement.finalize().unwrap();
+        assert!(settlement.global_state.settlement_finalized);
+        assert_eq!(settlement.global_state.settlement_supply, 8_500);
+
+        assert_error(settlement.finalize(), StableError::SettlementAlreadyFinalized);
+    }
+
+    #[test]
+    fn redemptions_pay_out_exactly_the_settled_collateral() {
+        test_utils::set_time(NOW);
+        let settled = [1_000_003, 777, 0];
+        let mut settlement = Settlement::new(&settled, 1_000, 0);
+        settlement.finalize().unwrap();
+
+        let mut paid = [0u64; 3];
+        for amount in [333, 1, 500, 99, 67] {
+            for (paid, payout) in paid.iter_mut().zip(settlement.redeem(amount).unwrap()) {
+                *paid += payout;
+            }
+        }
+
+        assert_eq!(paid, settled);
+        assert_eq!(settlement.global_state.settlement_supply, 0);
+        assert!(settlement.pools.iter().all(|pool| pool.settled_collateral == 0));
+    }
+
+    #[test]
+    fn redemptions_are_pro_rata_and_capped_by_the_supply() {
+        test_utils::set_time(NOW);
+        let mut settlement = Settlement::new(&[10_000, 3_000], 1_000, 0);
+        settlement.finalize().unwrap();
+
+        assert_eq!(settlement.redeem(250).unwrap(), vec![2_500, 750]);
+        assert_eq!(settlement.global_state.settlement_supply, 750);
+        assert_error(settlement.redeem(751), StableError::InvalidParameter);
+        assert_error(settlement.redeem(0), StableError::InvalidParameter);
+        assert_eq!(settlement.redeem(750).unwrap(), vec![7_500, 2_250]);
+    }
+}
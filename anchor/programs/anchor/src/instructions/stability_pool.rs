@@ -0,0 +1,386 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+use crate::utils::*;
+
+/// Create the stability pool of a collateral pool with its WUSD and collateral accounts (admin only)
+pub fn initialize_stability_pool(ctx: Context<InitializeStabilityPool>) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+
+    let stability_pool = &mut ctx.accounts.stability_pool;
+    stability_pool.pool = ctx.accounts.pool.key();
+    stability_pool.total_deposits = 0;
+    stability_pool.open_deposits = 0;
+    stability_pool.p = WAD;
+    stability_pool.current_scale = 0;
+    stability_pool.scale_sums = vec![0];
+    stability_pool.bump = ctx.bumps.stability_pool;
+
+    Ok(())
+}
+
+/// Deposit WUSD into a stability pool. Pending collateral gains are paid out first.
+pub fn deposit_stability(ctx: Context<DepositStability>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_STABILITY_POOL),
+        StableError::OperationPaused
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let deposit = &mut ctx.accounts.deposit;
+    if deposit.owner == Pubkey::default() {
+        deposit.owner = ctx.accounts.user.key();
+        deposit.stability_pool = ctx.accounts.stability_pool.key();
+        deposit.bump = ctx.bumps.deposit;
+    }
+
+    let stability_pool = &mut ctx.accounts.stability_pool;
+    let compounded = compounded_deposit(deposit, stability_pool)?;
+    let gain = collateral_gain(deposit, stability_pool)?
+        .min(ctx.accounts.stability_pool_collateral_account.amount);
+
+    let new_deposit = compounded
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+    snapshot_deposit(deposit, stability_pool, new_deposit);
+    stability_pool.total_deposits = stability_pool.total_deposits
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_stable_account.to_account_info(),
+                to: ctx.accounts.stability_pool_stable_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let pool_key = ctx.accounts.pool.key();
+
+    if gain > 0 {
+        let seeds = &[SEED_STABILITY_POOL, pool_key.as_ref(), &[ctx.accounts.stability_pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stability_pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.user_collateral_account.to_account_info(),
+                    authority: ctx.accounts.stability_pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            gain,
+        )?;
+
+        emit!(StabilityPoolGainClaimed {
+            user: ctx.accounts.user.key(),
+            pool: pool_key,
+            collateral_amount: gain,
+            timestamp: current_time,
+        });
+    }
+
+    emit!(StabilityPoolDeposited {
+        user: ctx.accounts.user.key(),
+        pool: pool_key,
+        amount,
+        new_deposit,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Withdraw up to `amount` WUSD from a stability pool, capped at the compounded deposit.
+/// Pending collateral gains are paid out as well.
+pub fn withdraw_stability(mut ctx: Context<WithdrawStability>, amount: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_STABILITY_POOL),
+        StableError::OperationPaused
+    );
+    require!(amount > 0, StableError::InvalidParameter);
+
+    let (withdrawn, new_deposit) = settle_stability_deposit(&mut ctx, amount)?;
+
+    emit!(StabilityPoolWithdrawn {
+        user: ctx.accounts.user.key(),
+        pool: ctx.accounts.pool.key(),
+        amount: withdrawn,
+        new_deposit,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Claim the collateral earned by a stability pool deposit
+pub fn claim_stability_gain(mut ctx: Context<WithdrawStability>) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_STABILITY_POOL),
+        StableError::OperationPaused
+    );
+
+    settle_stability_deposit(&mut ctx, 0)?;
+
+    Ok(())
+}
+
+/// Pay out a deposit's collateral gain and `amount` of its WUSD, then re-snapshot it.
+/// Returns (WUSD withdrawn, deposit left).
+fn settle_stability_deposit(ctx: &mut Context<WithdrawStability>, amount: u64) -> Result<(u64, u64)> {
+    let deposit = &mut ctx.accounts.deposit;
+    let stability_pool = &mut ctx.accounts.stability_pool;
+
+    let compounded = compounded_deposit(deposit, stability_pool)?;
+    let gain = collateral_gain(deposit, stability_pool)?
+        .min(ctx.accounts.stability_pool_collateral_account.amount);
+
+    let withdrawn = amount.min(compounded);
+    let new_deposit = compounded - withdrawn;
+    snapshot_deposit(deposit, stability_pool, new_deposit);
+    stability_pool.total_deposits = stability_pool.total_deposits
+        .checked_sub(withdrawn)
+        .ok_or(StableError::Overflow)?;
+
+    let pool_key = ctx.accounts.pool.key();
+    let seeds = &[SEED_STABILITY_POOL, pool_key.as_ref(), &[ctx.accounts.stability_pool.bump]];
+    let signer = &[&seeds[..]];
+
+    if withdrawn > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stability_pool_stable_account.to_account_info(),
+                    to: ctx.accounts.user_stable_account.to_account_info(),
+                    authority: ctx.accounts.stability_pool.to_account_info(),
+                },
+                signer,
+            ),
+            withdrawn,
+        )?;
+    }
+
+    if gain > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stability_pool_collateral_account.to_account_info(),
+                    to: ctx.accounts.user_collateral_account.to_account_info(),
+                    authority: ctx.accounts.stability_pool.to_account_info(),
+                },
+                signer,
+            ),
+            gain,
+        )?;
+
+        emit!(StabilityPoolGainClaimed {
+            user: ctx.accounts.user.key(),
+            pool: pool_key,
+            collateral_amount: gain,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    Ok((withdrawn, new_deposit))
+}
+
+#[derive(Accounts)]
+pub struct InitializeStabilityPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StabilityPool::INIT_SPACE,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump
+    )]
+    pub stability_pool: Box<Account<'info, StabilityPool>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = stability_pool,
+    )]
+    pub stability_pool_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = stability_pool,
+    )]
+    pub stability_pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositStability<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump = stability_pool.bump,
+    )]
+    pub stability_pool: Box<Account<'info, StabilityPool>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StabilityDeposit::INIT_SPACE,
+        seeds = [SEED_STABILITY_DEPOSIT, stability_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub deposit: Box<Account<'info, StabilityDeposit>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == global_state.stablecoin_mint,
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == pool.mint,
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStability<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Box<Account<'info, GlobalState>>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, CollateralPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL, pool.key().as_ref()],
+        bump = stability_pool.bump,
+    )]
+    pub stability_pool: Box<Account<'info, StabilityPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_DEPOSIT, stability_pool.key().as_ref(), user.key().as_ref()],
+        bump = deposit.bump,
+        constraint = deposit.owner == user.key() @ StableError::Unauthorized
+    )]
+    pub deposit: Box<Account<'info, StabilityDeposit>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_STABLE, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_STABILITY_POOL_COLLATERAL, pool.key().as_ref()],
+        bump,
+    )]
+    pub stability_pool_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_stable_account.owner == user.key(),
+        constraint = user_stable_account.mint == global_state.stablecoin_mint,
+    )]
+    pub user_stable_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_collateral_account.owner == user.key(),
+        constraint = user_collateral_account.mint == pool.mint,
+    )]
+    pub user_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
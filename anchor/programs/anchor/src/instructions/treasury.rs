@@ -0,0 +1,276 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::states::*;
+
+/// Create the protocol treasury: a WUSD token account owned by the treasury authority PDA.
+/// Stability fees paid in repay and liquidate_vault land here.
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+
+    Ok(())
+}
+
+/// Create a pool's collateral reserve, owned by the treasury authority.
+/// The protocol share of liquidation bonuses is paid into it.
+pub fn initialize_pool_reserve(ctx: Context<InitializePoolReserve>) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+
+    Ok(())
+}
+
+/// Move WUSD out of the treasury (admin only).
+/// Closed after shutdown, the treasury is burned when settlement is finalized.
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(!ctx.accounts.global_state.is_shutdown, StableError::SystemShutdown);
+    require!(amount > 0, StableError::InvalidParameter);
+    require!(
+        ctx.accounts.treasury.amount >= amount,
+        StableError::InsufficientTreasuryBalance
+    );
+
+    let seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    emit!(TreasuryWithdrawn {
+        admin: ctx.accounts.admin.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Move collateral out of a pool's reserve (admin only).
+/// Works after the pool is closed, emptying a closed pool's reserve also closes it.
+pub fn withdraw_pool_reserve(ctx: Context<WithdrawPoolReserve>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.global_state.admin == ctx.accounts.admin.key(),
+        StableError::Unauthorized
+    );
+    require!(amount > 0, StableError::InvalidParameter);
+    require!(
+        ctx.accounts.pool_reserve.amount >= amount,
+        StableError::InsufficientTreasuryBalance
+    );
+
+    let seeds = &[SEED_TREASURY_AUTHORITY, &[ctx.bumps.treasury_authority]];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_reserve.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    if ctx.accounts.pool.data_is_empty() && ctx.accounts.pool_reserve.amount == amount {
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.pool_reserve.to_account_info(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+
+    emit!(PoolReserveWithdrawn {
+        admin: ctx.accounts.admin.key(),
+        pool: ctx.accounts.pool.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        constraint = stablecoin_mint.key() == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub stablecoin_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_TREASURY],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = treasury_authority,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = treasury_authority,
+    )]
+    pub pool_reserve: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TREASURY],
+        bump,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == global_state.stablecoin_mint @ StableError::InvalidParameter
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPoolReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// CHECK: pool PDA of the collateral mint, may already be closed
+    #[account(
+        seeds = [SEED_POOL, collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: AccountInfo<'info>,
+
+    /// CHECK: PDA owning the treasury token accounts
+    #[account(
+        seeds = [SEED_TREASURY_AUTHORITY],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RESERVE, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_reserve: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == collateral_mint.key() @ StableError::InvalidParameter
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
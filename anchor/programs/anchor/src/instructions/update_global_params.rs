@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::*;
+
+/// Update global risk parameters (risk admin only).
+/// Every registered pool must be passed as a writable remaining account so interest
+/// accrued under the old stability fee is settled before the new fee applies.
+pub fn update_global_params<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateGlobalParams<'info>>,
+    debt_ceiling: u64,
+    stability_fee: u64,
+    liquidation_penalty: u64,
+) -> Result<()> {
+    validate_global_params(debt_ceiling, stability_fee, liquidation_penalty)?;
+
+    let global_state = &mut ctx.accounts.global_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    accrue_all_pools(
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_registry,
+        global_state,
+        current_time,
+    )?;
+    accrue_cross_margin_interest(global_state, current_time)?;
+
+    let old_debt_ceiling = global_state.debt_ceiling;
+    let old_stability_fee = global_state.stability_fee;
+    let old_liquidation_penalty = global_state.liquidation_penalty;
+
+    global_state.debt_ceiling = debt_ceiling;
+    global_state.stability_fee = stability_fee;
+    global_state.liquidation_penalty = liquidation_penalty;
+
+    emit!(GlobalParamsUpdated {
+        admin: ctx.accounts.risk_admin.key(),
+        old_debt_ceiling,
+        new_debt_ceiling: debt_ceiling,
+        old_stability_fee,
+        new_stability_fee: stability_fee,
+        old_liquidation_penalty,
+        new_liquidation_penalty: liquidation_penalty,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Bounds shared by initialize_global_state and update_global_params
+pub fn validate_global_params(
+    debt_ceiling: u64,
+    stability_fee: u64,
+    liquidation_penalty: u64,
+) -> Result<()> {
+    require!(debt_ceiling > 0, StableError::InvalidParameter);
+    require!(stability_fee <= MAX_STABILITY_FEE_BPS, StableError::InvalidParameter);
+    require!(
+        liquidation_penalty <= MAX_LIQUIDATION_PENALTY_BPS,
+        StableError::InvalidParameter
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateGlobalParams<'info> {
+    pub risk_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+        constraint = global_state.risk_admin == risk_admin.key() @ StableError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [SEED_POOL_REGISTRY],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+}
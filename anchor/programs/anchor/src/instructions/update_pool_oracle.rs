@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::*;
+
+/// Replace the oracle configuration of a collateral pool (oracle admin only)
+pub fn update_pool_oracle(
+    ctx: Context<UpdatePoolOracle>,
+    oracle_config: OracleConfig,
+) -> Result<()> {
+    validate_oracle_config(&oracle_config)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.oracle = oracle_config;
+
+    emit!(PoolOracleUpdated {
+        admin: ctx.accounts.oracle_admin.key(),
+        pool: pool.key(),
+        feed_id: oracle_config.feed_id,
+        price_account: oracle_config.price_account,
+        max_staleness: oracle_config.max_staleness,
+        max_confidence_bps: oracle_config.max_confidence_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolOracle<'info> {
+    pub oracle_admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_GLOBAL],
+        bump = global_state.bump,
+        constraint = global_state.oracle_admin == oracle_admin.key() @ StableError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [SEED_POOL, pool.mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, CollateralPool>,
+}
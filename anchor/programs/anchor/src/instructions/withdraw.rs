@@ -1,11 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use crate::constants::*;
 use crate::errors::*;
 use crate::states::*;
 use crate::events::*;
+use crate::utils::*;
 
 pub fn withdraw(ctx: Context<Withdraw>, shares_to_burn: u64) -> Result<()> {
+    require!(
+        !ctx.accounts.global_state.is_paused(PAUSE_WITHDRAW),
+        StableError::OperationPaused
+    );
     require!(shares_to_burn > 0, StableError::InvalidParameter);
 
     let pool_key = ctx.accounts.pool.key();
@@ -39,11 +44,45 @@ pub fn withdraw(ctx: Context<Withdraw>, shares_to_burn: u64) -> Result<()> {
         StableError::InvalidParameter
     );
 
-    if user_vault.debt_amount > 0 {
-        // TODO: Add oracle check for collateral ratio
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if user_vault.normalized_debt > 0 {
+        // during settlement the debt has to be netted by settle_vault before any collateral leaves
+        require!(!ctx.accounts.global_state.is_shutdown, StableError::VaultNotSettled);
+
+        accrue_pool_interest(pool, &mut ctx.accounts.global_state, current_time)?;
+        let total_debt = sync_vault_debt(user_vault, pool)?;
+
+        // remaining collateral is valued at the low end of the confidence interval
+        let price = load_collateral_price(&ctx.accounts.price_feed, pool)?.min_price()?;
+
+        // collateral left in the vault once this withdrawal goes through
+        let remaining_collateral = pool.total_collateral
+            .checked_sub(collateral_to_withdraw)
+            .ok_or(StableError::Overflow)?;
+        let remaining_shares = user_vault.collateral_shares
+            .checked_sub(shares_to_burn)
+            .ok_or(StableError::Overflow)?;
+        let remaining_total_shares = pool.total_shares
+            .checked_sub(shares_to_burn)
+            .ok_or(StableError::Overflow)?;
+
+        let remaining_collateral_amount = if remaining_total_shares == 0 {
+            0
+        } else {
+            calculate_amount_from_shares(remaining_shares, remaining_collateral, remaining_total_shares)?
+        };
+
+        let collateral_value = calculate_collateral_value(
+            remaining_collateral_amount,
+            price,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+        let collateral_ratio = calculate_collateral_ratio_bps(collateral_value, total_debt)?;
+
         require!(
-            user_vault.debt_amount == 0,
-            StableError::VaultNotEmpty
+            collateral_ratio >= pool.collateral_factor,
+            StableError::CollateralRatioTooLow
         );
     }
 
@@ -77,14 +116,16 @@ pub fn withdraw(ctx: Context<Withdraw>, shares_to_burn: u64) -> Result<()> {
     );
     token::transfer(transfer_ctx, collateral_to_withdraw)?;
     
-    user_vault.last_update = Clock::get()?.unix_timestamp;
+    user_vault.last_update = current_time;
+
+    update_sorted_vaults(&ctx.accounts.sorted_vaults, &ctx.accounts.user_vault.key(), &ctx.accounts.user_vault)?;
 
     emit!(WithdrawEvent {
         user: ctx.accounts.user.key(),
         pool: pool_key, 
         collateral_amount: collateral_to_withdraw,
         shares_burned: shares_to_burn,
-        timestamp: Clock::get()?.unix_timestamp,
+        timestamp: current_time,
     });
 
     Ok(())    
@@ -96,6 +137,7 @@ pub struct Withdraw<'info> {
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [SEED_GLOBAL],
         bump = global_state.bump,
     )]
@@ -108,6 +150,14 @@ pub struct Withdraw<'info> {
     )]
     pub pool_registry: Account<'info, PoolRegistry>,
 
+    #[account(
+        constraint = collateral_mint.key() == pool.mint @ StableError::InvalidPool
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// CHECK: MockPriceFeed PDA or Pyth PriceUpdateV2 - owner, seeds and feed validated by load_collateral_price, only read when the vault has debt
+    pub price_feed: AccountInfo<'info>,
+
     #[account(
         mut, 
         constraint = user_collateral_account.owner == user.key(),
@@ -132,12 +182,19 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref()],
+        seeds = [SEED_VAULT, user.key().as_ref(), pool.key().as_ref(), user_vault.vault_index.to_le_bytes().as_ref()],
         bump = user_vault.bump,
         constraint = user_vault.owner == user.key() @ StableError::Unauthorized,
         constraint = user_vault.pool == pool.key() @ StableError::InvalidPool
     )]
     pub user_vault: Account<'info, UserVault>,
 
+    #[account(
+        mut,
+        seeds = [SEED_SORTED_VAULTS, pool.key().as_ref()],
+        bump = sorted_vaults.load()?.bump,
+    )]
+    pub sorted_vaults: AccountLoader<'info, SortedVaults>,
+
     pub token_program: Program<'info, Token>,
 }
\ No newline at end of file
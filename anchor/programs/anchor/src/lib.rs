@@ -1,15 +1,30 @@
+// `#[program]` emits its IDL handlers into a crate-level module that calls the deprecated
+// AccountInfo::realloc (anchor 0.31). They can't carry an attribute of their own, so the
+// allow sits here and every module of ours turns the lint back on.
+#![allow(deprecated)]
 use anchor_lang::prelude::*;
 declare_id!("DbzvMaPVGPJrGW2t16dn6sgu8rnnpXZLNiqhtZ61rUFz");
 
+#[warn(deprecated)]
 pub mod states;
+#[warn(deprecated)]
 pub mod errors;
+#[warn(deprecated)]
 pub mod utils;
+#[warn(deprecated)]
 pub mod constants;
+#[warn(deprecated)]
 pub mod events;
 
+#[warn(deprecated)]
 pub mod instructions;
+#[cfg(test)]
+#[warn(deprecated)]
+pub mod test_utils;
 use instructions::*;
+use states::{AuctionConfig, OracleConfig};
 
+#[warn(deprecated)]
 #[program]
 pub mod anchor {
     use super::*;
@@ -26,6 +41,81 @@ pub mod anchor {
         instructions::initialize_global_state(ctx, debt_ceiling, stability_fee, liquidation_penalty)
     }
 
+    pub fn update_global_params<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateGlobalParams<'info>>,
+        debt_ceiling: u64,
+        stability_fee: u64,
+        liquidation_penalty: u64,
+    ) -> Result<()> {
+        instructions::update_global_params(ctx, debt_ceiling, stability_fee, liquidation_penalty)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn set_roles(
+        ctx: Context<SetRoles>,
+        risk_admin: Pubkey,
+        oracle_admin: Pubkey,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        instructions::set_roles(ctx, risk_admin, oracle_admin, guardian)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused_operations: u32) -> Result<()> {
+        instructions::set_paused(ctx, paused_operations)
+    }
+
+    pub fn shutdown(ctx: Context<Shutdown>) -> Result<()> {
+        instructions::shutdown(ctx)
+    }
+
+    pub fn cage_pool(ctx: Context<CagePool>) -> Result<()> {
+        instructions::cage_pool(ctx)
+    }
+
+    pub fn settle_vault(ctx: Context<SettleVault>) -> Result<()> {
+        instructions::settle_vault(ctx)
+    }
+
+    pub fn settle_cross_margin(ctx: Context<SettleCrossMargin>) -> Result<()> {
+        instructions::settle_cross_margin(ctx)
+    }
+
+    pub fn finalize_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeSettlement<'info>>,
+    ) -> Result<()> {
+        instructions::finalize_settlement(ctx)
+    }
+
+    pub fn redeem_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemSettlement<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::redeem_settlement(ctx, amount)
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        instructions::initialize_treasury(ctx)
+    }
+
+    pub fn initialize_pool_reserve(ctx: Context<InitializePoolReserve>) -> Result<()> {
+        instructions::initialize_pool_reserve(ctx)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::withdraw_treasury(ctx, amount)
+    }
+
+    pub fn withdraw_pool_reserve(ctx: Context<WithdrawPoolReserve>, amount: u64) -> Result<()> {
+        instructions::withdraw_pool_reserve(ctx, amount)
+    }
+
     pub fn initialize_pool_registry(
         ctx: Context<InitializePoolRegistry>,
     ) -> Result<()> {
@@ -34,9 +124,10 @@ pub mod anchor {
 
     pub fn deposit(
         ctx: Context<Deposit>,
+        vault_index: u16,
         collateral_amount: u64,
     ) -> Result<()> {
-        instructions::deposit(ctx, collateral_amount)
+        instructions::deposit(ctx, vault_index, collateral_amount)
     }
 
     pub fn withdraw(
@@ -58,16 +149,249 @@ pub mod anchor {
         instructions::liquidate_vault(ctx, debt_to_repay)
     }
 
+    pub fn liquidate_bad_debt(ctx: Context<LiquidateBadDebt>) -> Result<()> {
+        instructions::liquidate_bad_debt(ctx)
+    }
+
+    pub fn heal_bad_debt(ctx: Context<HealBadDebt>, amount: u64) -> Result<()> {
+        instructions::heal_bad_debt(ctx, amount)
+    }
+
+    pub fn recapitalize(ctx: Context<Recapitalize>, amount: u64) -> Result<()> {
+        instructions::recapitalize(ctx, amount)
+    }
+
+    pub fn start_auction(ctx: Context<StartAuction>) -> Result<()> {
+        instructions::start_auction(ctx)
+    }
+
+    pub fn take_auction(ctx: Context<TakeAuction>, max_collateral: u64, max_price: i64) -> Result<()> {
+        instructions::take_auction(ctx, max_collateral, max_price)
+    }
+
+    pub fn reset_auction(ctx: Context<ResetAuction>) -> Result<()> {
+        instructions::reset_auction(ctx)
+    }
+
+    pub fn yank_auction(ctx: Context<YankAuction>) -> Result<()> {
+        instructions::yank_auction(ctx)
+    }
+
+    pub fn initialize_stability_pool(ctx: Context<InitializeStabilityPool>) -> Result<()> {
+        instructions::initialize_stability_pool(ctx)
+    }
+
+    pub fn deposit_stability(ctx: Context<DepositStability>, amount: u64) -> Result<()> {
+        instructions::deposit_stability(ctx, amount)
+    }
+
+    pub fn withdraw_stability(ctx: Context<WithdrawStability>, amount: u64) -> Result<()> {
+        instructions::withdraw_stability(ctx, amount)
+    }
+
+    pub fn claim_stability_gain(ctx: Context<WithdrawStability>) -> Result<()> {
+        instructions::claim_stability_gain(ctx)
+    }
+
+    pub fn initialize_sorted_vaults(ctx: Context<InitializeSortedVaults>) -> Result<()> {
+        instructions::initialize_sorted_vaults(ctx)
+    }
+
+    pub fn sync_sorted_vault(ctx: Context<SyncSortedVault>) -> Result<()> {
+        instructions::sync_sorted_vault(ctx)
+    }
+
+    pub fn redeem<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Redeem<'info>>,
+        amount: u64,
+        max_fee_bps: u64,
+    ) -> Result<()> {
+        instructions::redeem(ctx, amount, max_fee_bps)
+    }
+
+    pub fn initialize_psm(
+        ctx: Context<InitializePsm>,
+        tin_bps: u64,
+        tout_bps: u64,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        instructions::initialize_psm(ctx, tin_bps, tout_bps, debt_ceiling)
+    }
+
+    pub fn update_psm_params(
+        ctx: Context<UpdatePsmParams>,
+        tin_bps: u64,
+        tout_bps: u64,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        instructions::update_psm_params(ctx, tin_bps, tout_bps, debt_ceiling)
+    }
+
+    pub fn swap_to_stable(ctx: Context<SwapToStable>, amount: u64) -> Result<()> {
+        instructions::swap_to_stable(ctx, amount)
+    }
+
+    pub fn swap_from_stable(ctx: Context<SwapFromStable>, amount: u64) -> Result<()> {
+        instructions::swap_from_stable(ctx, amount)
+    }
+
+    pub fn update_flash_mint_params(
+        ctx: Context<UpdateFlashMintParams>,
+        fee_bps: u64,
+        cap: u64,
+    ) -> Result<()> {
+        instructions::update_flash_mint_params(ctx, fee_bps, cap)
+    }
+
+    pub fn flash_mint(ctx: Context<FlashMint>, amount: u64) -> Result<()> {
+        instructions::flash_mint(ctx, amount)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashRepay>, amount: u64) -> Result<()> {
+        instructions::flash_repay(ctx, amount)
+    }
+
+    pub fn initialize_savings(ctx: Context<InitializeSavings>, savings_rate_bps: u64) -> Result<()> {
+        instructions::initialize_savings(ctx, savings_rate_bps)
+    }
+
+    pub fn set_savings_rate(ctx: Context<SetSavingsRate>, savings_rate_bps: u64) -> Result<()> {
+        instructions::set_savings_rate(ctx, savings_rate_bps)
+    }
+
+    pub fn accrue_savings_interest(ctx: Context<AccrueSavings>) -> Result<()> {
+        instructions::accrue_savings_interest(ctx)
+    }
+
+    pub fn deposit_savings(ctx: Context<SavingsAction>, amount: u64) -> Result<()> {
+        instructions::deposit_savings(ctx, amount)
+    }
+
+    pub fn redeem_savings(ctx: Context<SavingsAction>, shares: u64) -> Result<()> {
+        instructions::redeem_savings(ctx, shares)
+    }
+
+    pub fn open_cross_margin_account(ctx: Context<OpenCrossMarginAccount>) -> Result<()> {
+        instructions::open_cross_margin_account(ctx)
+    }
+
+    pub fn cross_margin_deposit(ctx: Context<CrossMarginDeposit>, collateral_amount: u64) -> Result<()> {
+        instructions::cross_margin_deposit(ctx, collateral_amount)
+    }
+
+    pub fn cross_margin_withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrossMarginWithdraw<'info>>,
+        shares_to_burn: u64,
+    ) -> Result<()> {
+        instructions::cross_margin_withdraw(ctx, shares_to_burn)
+    }
+
+    pub fn cross_margin_mint<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrossMarginMint<'info>>,
+        stable_amount: u64,
+    ) -> Result<()> {
+        instructions::cross_margin_mint(ctx, stable_amount)
+    }
+
+    pub fn cross_margin_repay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrossMarginRepay<'info>>,
+        repay_amount: u64,
+    ) -> Result<()> {
+        instructions::cross_margin_repay(ctx, repay_amount)
+    }
+
+    pub fn cross_margin_liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrossMarginLiquidate<'info>>,
+        debt_to_repay: u64,
+    ) -> Result<()> {
+        instructions::cross_margin_liquidate(ctx, debt_to_repay)
+    }
+
+    pub fn cross_margin_clear_bad_debt(ctx: Context<CrossMarginClearBadDebt>) -> Result<()> {
+        instructions::cross_margin_clear_bad_debt(ctx)
+    }
+
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         collateral_factor: u64,
         liquidation_factor: u64,
         interest_rate_model: Pubkey,
+        oracle_config: OracleConfig,
+    ) -> Result<()> {
+        instructions::initialize_pool(ctx, collateral_factor, liquidation_factor, interest_rate_model, oracle_config)
+    }
+
+    pub fn update_pool_params(
+        ctx: Context<UpdatePool>,
+        collateral_factor: u64,
+        liquidation_factor: u64,
+    ) -> Result<()> {
+        instructions::update_pool_params(ctx, collateral_factor, liquidation_factor)
+    }
+
+    pub fn update_pool_liquidation_params(
+        ctx: Context<UpdatePool>,
+        liquidation_penalty_bps: u64,
+        close_factor_bps: u64,
+        debt_dust: u64,
+        protocol_liquidation_share: u64,
+    ) -> Result<()> {
+        instructions::update_pool_liquidation_params(
+            ctx,
+            liquidation_penalty_bps,
+            close_factor_bps,
+            debt_dust,
+            protocol_liquidation_share,
+        )
+    }
+
+    pub fn update_pool_auction_config(
+        ctx: Context<UpdatePool>,
+        auction_config: AuctionConfig,
+    ) -> Result<()> {
+        instructions::update_pool_auction_config(ctx, auction_config)
+    }
+
+    pub fn update_pool_redemption_fee(ctx: Context<UpdatePool>, redemption_fee_bps: u64) -> Result<()> {
+        instructions::update_pool_redemption_fee(ctx, redemption_fee_bps)
+    }
+
+    pub fn set_pool_active(ctx: Context<UpdatePool>, is_active: bool) -> Result<()> {
+        instructions::set_pool_active(ctx, is_active)
+    }
+
+    pub fn set_pool_reduce_only(ctx: Context<UpdatePool>, reduce_only: bool) -> Result<()> {
+        instructions::set_pool_reduce_only(ctx, reduce_only)
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool(ctx)
+    }
+
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        instructions::close_vault(ctx)
+    }
+
+    pub fn update_pool_oracle(
+        ctx: Context<UpdatePoolOracle>,
+        oracle_config: OracleConfig,
     ) -> Result<()> {
-        instructions::initialize_pool(ctx, collateral_factor, liquidation_factor, interest_rate_model)
+        instructions::update_pool_oracle(ctx, oracle_config)
+    }
+
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        instructions::migrate_global_state(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>, oracle_config: OracleConfig) -> Result<()> {
+        instructions::migrate_pool(ctx, oracle_config)
+    }
+
+    pub fn migrate_vault(ctx: Context<MigrateVault>, vault_index: u16) -> Result<()> {
+        instructions::migrate_vault(ctx, vault_index)
     }
 
-    /// Set mock price for local testing (admin only)
+    /// Set mock price for local testing (oracle admin only)
     pub fn set_mock_price(ctx: Context<SetMockPrice>, price: i64, confidence: u64) -> Result<()> {
         instructions::set_mock_price(ctx, price, confidence)
     }
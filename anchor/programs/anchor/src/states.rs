@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::constants::{MAX_CROSS_MARGIN_POSITIONS, MAX_SORTED_VAULTS};
 
 #[account]
 #[derive(InitSpace)]
@@ -7,11 +8,22 @@ pub struct UserVault {
     pub pool: Pubkey,            // 32 bytes
     
     pub collateral_shares: u64,  // 8 bytes
-    pub debt_amount: u64,        // 8 bytes
-    pub accrued_interest: u64,   // 8 bytes
+    pub debt_amount: u64,        // 8 bytes - principal outstanding
+    pub accrued_interest: u64,   // 8 bytes - interest owed as of last_update
+    pub normalized_debt: u64,    // 8 bytes - debt / pool.rate_index, source of truth for what is owed
     
     pub last_update: i64,        // 8 bytes
     pub bump: u8,                // 1 byte
+    pub vault_index: u16,        // 2 bytes - last vault seed, lets a user hold several vaults per pool
+}
+
+/// Hands out vault indices to a user's new vaults
+#[account]
+#[derive(InitSpace)]
+pub struct UserVaultCounter {
+    pub owner: Pubkey,           // 32 bytes
+    pub next_index: u16,         // 2 bytes - index of the user's next new vault
+    pub bump: u8,                // 1 byte
 }
 
 #[account]
@@ -23,8 +35,43 @@ pub struct CollateralPool {
     pub collateral_factor: u64,   // 8 bytes
     pub liquidation_factor: u64,  // 8 bytes
     pub interest_rate_model: Pubkey, // 32 bytes
-    pub is_active: bool,          // 1 byte
+    pub is_active: bool,          // 1 byte - false once retired: no deposits or mints
     pub bump: u8,                 // 1 byte
+    pub reduce_only: bool,        // 1 byte - deposits and mints blocked, exits still allowed
+    pub oracle: OracleConfig,     // 80 bytes
+    pub rate_index: u128,         // 16 bytes - cumulative borrow index (WAD), starts at 1.0
+    pub last_accrual: i64,        // 8 bytes - last time rate_index was rolled forward
+    pub total_normalized_debt: u128, // 16 bytes - sum of vault normalized debt
+    pub is_caged: bool,           // 1 byte - frozen for global settlement, rate_index no longer accrues
+    pub final_price: i64,         // 8 bytes - settlement price (8 decimals), set by cage_pool
+    pub settled_collateral: u64,  // 8 bytes - collateral netted from vaults, redeemable by WUSD holders
+    pub settlement_shortfall: u64, // 8 bytes - settled debt the vault collateral did not cover
+    pub protocol_liquidation_share: u64, // 8 bytes - bps of the liquidation bonus sent to the pool reserve
+    pub liquidation_penalty_bps: u64,  // 8 bytes - liquidator bonus on top of the debt repaid
+    pub close_factor_bps: u64,    // 8 bytes - max share of a vault's debt repaid per liquidation
+    pub debt_dust: u64,           // 8 bytes - below this remaining debt a vault can be liquidated in full
+    pub auction: AuctionConfig,   // 41 bytes - Dutch auction price curve, disabled while duration is 0
+    pub auction_collateral: u64,  // 8 bytes - collateral held by running auctions, not part of total_collateral
+    pub redemption_fee_bps: u64,  // 8 bytes - share of redeemed collateral kept by the pool reserve
+}
+
+/// Per-pool oracle settings used by every instruction that prices collateral
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct OracleConfig {
+    pub feed_id: [u8; 32],        // 32 bytes - Pyth feed id (collateral/USD)
+    pub price_account: Pubkey,    // 32 bytes - expected price account, default = any account carrying the feed
+    pub max_staleness: u64,       // 8 bytes - max price age in seconds
+    pub max_confidence_bps: u64,  // 8 bytes - max confidence / price ratio in bps
+}
+
+/// Per-pool Dutch auction price curve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AuctionConfig {
+    pub curve: u8,                // 1 byte - AUCTION_CURVE_*
+    pub buf_bps: u64,             // 8 bytes - starting price as bps of the oracle price (>= 10,000)
+    pub duration: u64,            // 8 bytes - seconds until the auction needs a reset
+    pub step: u64,                // 8 bytes - stairstep: seconds between price cuts
+    pub cut_bps: u64,             // 8 bytes - stairstep: price kept at each cut, in bps
 }
 
 #[account]
@@ -33,12 +80,36 @@ pub struct GlobalState {
     pub admin: Pubkey,                    // 32 bytes
     pub stablecoin_mint: Pubkey,          // 32 bytes
     pub governance_token_mint: Pubkey,    // 32 bytes
-    pub total_debt: u64,                  // 8 bytes
+    pub total_debt: u64,                  // 8 bytes - owed debt incl. interest as of each pool's last accrual
     pub debt_ceiling: u64,                // 8 bytes
     pub stability_fee: u64,               // 8 bytes
-    pub liquidation_penalty: u64,         // 8 bytes
+    pub liquidation_penalty: u64,         // 8 bytes - default penalty for new pools
     pub pool_count: u64,                  // 8 bytes
     pub bump: u8,                         // 1 byte
+    pub pending_admin: Pubkey,            // 32 bytes - proposed admin, default when none
+    pub risk_admin: Pubkey,               // 32 bytes - global and pool risk parameters
+    pub oracle_admin: Pubkey,             // 32 bytes - oracle configs and mock feeds
+    pub guardian: Pubkey,                 // 32 bytes - may only pause / de-risk
+    pub paused_operations: u32,           // 4 bytes - PAUSE_* bitmask
+    pub is_shutdown: bool,                // 1 byte - global settlement started
+    pub shutdown_time: i64,               // 8 bytes
+    pub settlement_finalized: bool,       // 1 byte - WUSD redemptions open
+    pub settlement_supply: u64,           // 8 bytes - WUSD supply snapshot at finalization
+    pub total_fees_collected: u64,        // 8 bytes - stability fees routed to the treasury
+    pub bad_debt: u64,                    // 8 bytes - WUSD left unbacked by underwater liquidations
+    pub auction_debt: u64,                // 8 bytes - vault debt moved into running auctions
+    pub flash_mint_fee_bps: u64,          // 8 bytes - fee charged on flash_repay
+    pub flash_mint_cap: u64,              // 8 bytes - max WUSD per flash_mint, 0 disables flash mints
+    pub flash_minted: u64,                // 8 bytes - flash minted WUSD awaiting flash_repay in this transaction
+    pub cross_rate_index: u128,           // 16 bytes - cumulative borrow index of cross-margin debt (WAD)
+    pub cross_last_accrual: i64,          // 8 bytes
+    pub cross_normalized_debt: u128,      // 16 bytes - sum of cross-margin account normalized debt
+}
+
+impl GlobalState {
+    pub fn is_paused(&self, operation: u32) -> bool {
+        self.paused_operations & operation != 0
+    }
 }
 
 #[account]
@@ -60,4 +131,112 @@ pub struct MockPriceFeed {
     pub confidence: u64,         // 8 decimals
     pub last_update: i64,        // unix timestamp
     pub bump: u8,
-}
\ No newline at end of file
+}
+/// Dutch auction selling the collateral of a liquidated vault
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    pub vault: Pubkey,           // 32 bytes - liquidated vault, receives leftover collateral
+    pub pool: Pubkey,            // 32 bytes
+    pub keeper: Pubkey,          // 32 bytes - started the auction, gets the rent back
+    pub debt: u64,               // 8 bytes - vault debt still to be burned
+    pub tab: u64,                // 8 bytes - WUSD still to raise (debt + penalty)
+    pub lot: u64,                // 8 bytes - collateral left for sale
+    pub start_price: i64,        // 8 bytes - 8 decimals
+    pub start_time: i64,         // 8 bytes
+    pub bump: u8,                // 1 byte
+}
+
+/// Per-pool stability pool: WUSD deposits that absorb liquidated debt in exchange
+/// for the seized collateral, tracked with Liquity's product-sum scheme
+#[account]
+#[derive(InitSpace)]
+pub struct StabilityPool {
+    pub pool: Pubkey,            // 32 bytes - collateral pool it absorbs liquidations for
+    pub total_deposits: u64,     // 8 bytes - WUSD currently deposited
+    pub open_deposits: u64,      // 8 bytes - deposits with a non-zero snapshot, they may still hold collateral gains
+    pub p: u128,                 // 16 bytes - running product (WAD), deposits compound by P / P_snapshot
+    pub current_scale: u64,      // 8 bytes - bumped every time P is rescaled by 1e9
+
+    #[max_len(8)]
+    pub scale_sums: Vec<u128>,   // collateral gain sum S per scale
+    pub bump: u8,                // 1 byte
+}
+
+/// A user's deposit in a stability pool
+#[account]
+#[derive(InitSpace)]
+pub struct StabilityDeposit {
+    pub owner: Pubkey,           // 32 bytes
+    pub stability_pool: Pubkey,  // 32 bytes
+    pub amount: u64,             // 8 bytes - deposit as of the snapshot
+    pub snapshot_p: u128,        // 16 bytes
+    pub snapshot_s: u128,        // 16 bytes
+    pub snapshot_scale: u64,     // 8 bytes
+    pub bump: u8,                // 1 byte
+}
+
+/// Per-pool index of indebted vaults ordered by collateral shares per unit of
+/// normalized debt, riskiest first. Every vault in a pool shares the same share price
+/// and rate index, so the order only changes when a vault itself changes.
+/// Holds the MAX_SORTED_VAULTS riskiest vaults, safer ones drop off the end.
+#[account(zero_copy)]
+pub struct SortedVaults {
+    pub pool: Pubkey,            // 32 bytes
+    pub len: u32,                // 4 bytes
+    pub bump: u8,                // 1 byte
+    pub _padding: [u8; 3],       // 3 bytes
+    pub entries: [SortedVaultEntry; MAX_SORTED_VAULTS], // 48 bytes each
+}
+
+#[zero_copy]
+pub struct SortedVaultEntry {
+    pub vault: Pubkey,           // 32 bytes
+    pub collateral_shares: u64,  // 8 bytes
+    pub normalized_debt: u64,    // 8 bytes
+}
+
+/// Peg stability module for one stable collateral: swaps it for WUSD 1:1 minus a fee
+#[account]
+#[derive(InitSpace)]
+pub struct Psm {
+    pub mint: Pubkey,            // 32 bytes - stable collateral, same decimals as WUSD
+    pub tin_bps: u64,            // 8 bytes - fee on swaps into WUSD
+    pub tout_bps: u64,           // 8 bytes - fee on swaps out of WUSD
+    pub debt_ceiling: u64,       // 8 bytes - max WUSD minted against the reserve
+    pub debt: u64,               // 8 bytes - WUSD minted against the reserve, equals the reserve balance
+    pub bump: u8,                // 1 byte
+}
+
+/// Savings module: WUSD deposits earning the savings rate, represented by sWUSD shares
+#[account]
+#[derive(InitSpace)]
+pub struct Savings {
+    pub share_mint: Pubkey,      // 32 bytes - sWUSD mint, this account is its authority
+    pub total_assets: u64,       // 8 bytes - WUSD owed to sWUSD holders, grows with accrual
+    pub savings_rate_bps: u64,   // 8 bytes - annual rate, paid out of the treasury
+    pub last_accrual: i64,       // 8 bytes
+    pub bump: u8,                // 1 byte
+}
+
+/// Cross-margin account: collateral shares in several pools backing a single debt
+#[account]
+#[derive(InitSpace)]
+pub struct CrossMarginAccount {
+    pub owner: Pubkey,           // 32 bytes
+    #[max_len(MAX_CROSS_MARGIN_POSITIONS)]
+    pub positions: Vec<CrossMarginPosition>, // 4 + 5 * 41 bytes
+    pub debt_amount: u64,        // 8 bytes - principal outstanding
+    pub accrued_interest: u64,   // 8 bytes - interest owed as of last_update
+    pub normalized_debt: u64,    // 8 bytes - debt / global_state.cross_rate_index
+    pub last_update: i64,        // 8 bytes
+    pub bump: u8,                // 1 byte
+}
+
+/// Collateral a cross-margin account holds in one pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct CrossMarginPosition {
+    pub pool: Pubkey,            // 32 bytes
+    pub collateral_shares: u64,  // 8 bytes - shares of pool.total_collateral
+    pub decimals: u8,            // 1 byte - collateral mint decimals
+}
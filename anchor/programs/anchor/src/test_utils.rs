@@ -0,0 +1,403 @@
+//! Shared fixtures for unit tests: program state as the initialize instructions leave it,
+//! in-memory accounts, and a runner that validates an instruction's accounts, calls its
+//! handler and writes the accounts back the way the runtime dispatch does.
+//! CPIs are no-ops off-chain, so token balances never move: tests assert on program state.
+
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::sync::Once;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::TRANSACTION_LEVEL_STACK_HEIGHT;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::sysvar::instructions::{
+    construct_instructions_data, BorrowedAccountMeta, BorrowedInstruction,
+    ID as INSTRUCTIONS_SYSVAR_ID,
+};
+use anchor_lang::{Bumps, Space};
+use anchor_spl::token::spl_token;
+use pyth_solana_receiver_sdk::price_update::{PriceFeedMessage, PriceUpdateV2, VerificationLevel};
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::WAD;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(0) };
+    static STACK_HEIGHT: Cell<u64> = const { Cell::new(TRANSACTION_LEVEL_STACK_HEIGHT as u64) };
+}
+
+/// Syscalls backing `Clock::get`, `Rent::get` and `get_stack_height` off-chain.
+/// Tests run at the time set with `set_time`, as a top-level instruction unless
+/// `set_stack_height` says otherwise.
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW.with(Cell::get),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        STACK_HEIGHT.with(Cell::get)
+    }
+}
+
+fn install_syscalls() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscalls));
+    });
+}
+
+/// Set the unix timestamp `Clock::get` returns on the current test thread
+pub fn set_time(unix_timestamp: i64) {
+    install_syscalls();
+    NOW.with(|now| now.set(unix_timestamp));
+}
+
+/// Set the instruction stack height `get_stack_height` returns on the current test thread
+pub fn set_stack_height(stack_height: u64) {
+    install_syscalls();
+    STACK_HEIGHT.with(|height| height.set(stack_height));
+}
+
+/// An account as the runtime hands it to the program
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    /// A writable, rent exempt account
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            owner,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            is_signer: false,
+            is_writable: true,
+            executable: false,
+        }
+    }
+
+    /// A system account signing the transaction
+    pub fn signer(key: Pubkey) -> Self {
+        Self {
+            is_signer: true,
+            ..Self::new(key, anchor_lang::system_program::ID, Vec::new())
+        }
+    }
+
+    /// An account of this program holding `state`, sized like `init` sizes it
+    pub fn state<T: AccountSerialize + Space>(key: Pubkey, state: &T) -> Self {
+        let mut data = Vec::with_capacity(8 + T::INIT_SPACE);
+        state.try_serialize(&mut data).unwrap();
+        data.resize(8 + T::INIT_SPACE, 0);
+        Self::new(key, crate::ID, data)
+    }
+
+    /// An SPL token mint
+    pub fn mint(key: Pubkey, authority: Pubkey, supply: u64) -> Self {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply,
+            decimals: STABLE_DECIMALS as u8,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        Self::new(key, spl_token::ID, data)
+    }
+
+    /// An SPL token account
+    pub fn token(key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        Self::new(key, spl_token::ID, data)
+    }
+
+    /// A deployed program
+    pub fn program(id: Pubkey) -> Self {
+        Self {
+            is_writable: false,
+            executable: true,
+            ..Self::new(id, anchor_lang::solana_program::bpf_loader_upgradeable::ID, Vec::new())
+        }
+    }
+
+    /// The instructions sysvar of a transaction made of `instructions`, executing `current`
+    pub fn instructions_sysvar(instructions: &[(Pubkey, Vec<u8>)], current: u16) -> Self {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|(program_id, data)| BorrowedInstruction {
+                program_id,
+                accounts: Vec::<BorrowedAccountMeta>::new(),
+                data,
+            })
+            .collect();
+        // the sysvar ends with the index of the executing instruction
+        let mut data = construct_instructions_data(&borrowed);
+        let index_offset = data.len() - 2;
+        data[index_offset..].copy_from_slice(&current.to_le_bytes());
+        Self {
+            is_writable: false,
+            ..Self::new(INSTRUCTIONS_SYSVAR_ID, anchor_lang::solana_program::sysvar::ID, data)
+        }
+    }
+
+    /// A Pyth receiver price update for `feed_id`, 8 decimals
+    pub fn pyth_price(key: Pubkey, feed_id: [u8; 32], price: i64, conf: u64, publish_time: i64) -> Self {
+        let update = PriceUpdateV2 {
+            write_authority: Pubkey::new_unique(),
+            verification_level: VerificationLevel::Full,
+            price_message: PriceFeedMessage {
+                feed_id,
+                price,
+                conf,
+                exponent: -(PRICE_DECIMALS as i32),
+                publish_time,
+                prev_publish_time: publish_time,
+                ema_price: price,
+                ema_conf: conf,
+            },
+            posted_slot: 0,
+        };
+        let mut data = Vec::with_capacity(PriceUpdateV2::LEN);
+        update.try_serialize(&mut data).unwrap();
+        Self::new(key, pyth_solana_receiver_sdk::ID, data)
+    }
+
+    pub fn read_only(self) -> Self {
+        Self {
+            is_writable: false,
+            ..self
+        }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+
+    /// Deserialize the program state the account holds
+    pub fn load<T: AccountDeserialize>(&self) -> T {
+        T::try_deserialize(&mut &self.data[..]).unwrap()
+    }
+}
+
+/// PDA of this program
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::ID).0
+}
+
+/// Validate `infos` against the `Accounts` struct `T`, run `handler` on the context and
+/// persist the accounts. Accounts `T` does not consume become the remaining accounts.
+/// `init` constraints can't be exercised here since the system program CPI is a no-op.
+pub fn run<'info, T, F>(infos: &'info [AccountInfo<'info>], handler: F) -> Result<()>
+where
+    T: Bumps + Accounts<'info, T::Bumps> + AccountsExit<'info>,
+    T::Bumps: Default,
+    F: FnOnce(Context<'_, '_, 'info, 'info, T>) -> Result<()>,
+{
+    install_syscalls();
+    let mut remaining = infos;
+    let mut bumps = T::Bumps::default();
+    let mut accounts = T::try_accounts(&crate::ID, &mut remaining, &[], &mut bumps, &mut BTreeSet::new())?;
+    handler(Context::new(&crate::ID, &mut accounts, remaining, bumps))?;
+    accounts.exit(&crate::ID)
+}
+
+/// Run an instruction handler over a slice of `TestAccount`s, see `run`
+macro_rules! run_instruction {
+    ($accounts:ty, $test_accounts:expr, $handler:expr) => {{
+        let infos: Vec<_> = $test_accounts.iter_mut().map(|account| account.info()).collect();
+        $crate::test_utils::run::<$accounts, _>(&infos, $handler)
+    }};
+}
+pub(crate) use run_instruction;
+
+/// Assert that `result` failed with the program error `expected`
+pub fn assert_error<T>(result: Result<T>, expected: StableError) {
+    match result {
+        Ok(_) => panic!("expected {expected:?}, got Ok"),
+        Err(error) => assert_eq!(error, Error::from(expected)),
+    }
+}
+
+/// Global state as `initialize_global_state` leaves it, with `admin` holding every role
+pub fn global_state(admin: Pubkey, stablecoin_mint: Pubkey) -> GlobalState {
+    GlobalState {
+        admin,
+        stablecoin_mint,
+        governance_token_mint: Pubkey::new_unique(),
+        total_debt: 0,
+        debt_ceiling: 1_000_000_000_000,
+        stability_fee: 200,
+        liquidation_penalty: 500,
+        pool_count: 0,
+        bump: Pubkey::find_program_address(&[SEED_GLOBAL], &crate::ID).1,
+        pending_admin: Pubkey::default(),
+        risk_admin: admin,
+        oracle_admin: admin,
+        guardian: admin,
+        paused_operations: 0,
+        is_shutdown: false,
+        shutdown_time: 0,
+        settlement_finalized: false,
+        settlement_supply: 0,
+        total_fees_collected: 0,
+        bad_debt: 0,
+        auction_debt: 0,
+        flash_mint_fee_bps: 0,
+        flash_mint_cap: 0,
+        flash_minted: 0,
+        cross_rate_index: WAD,
+        cross_last_accrual: 0,
+        cross_normalized_debt: 0,
+    }
+}
+
+/// A pool as `initialize_pool` leaves it: 150% to mint, 120% before liquidation,
+/// priced by the Pyth feed `feed_id` at most a minute old
+pub fn collateral_pool(mint: Pubkey, feed_id: [u8; 32]) -> CollateralPool {
+    CollateralPool {
+        mint,
+        total_collateral: 0,
+        total_shares: 0,
+        collateral_factor: 15_000,
+        liquidation_factor: 12_000,
+        interest_rate_model: Pubkey::default(),
+        is_active: true,
+        bump: Pubkey::find_program_address(&[SEED_POOL, mint.as_ref()], &crate::ID).1,
+        reduce_only: false,
+        oracle: OracleConfig {
+            feed_id,
+            price_account: Pubkey::default(),
+            max_staleness: 60,
+            max_confidence_bps: 200,
+        },
+        rate_index: WAD,
+        last_accrual: 0,
+        total_normalized_debt: 0,
+        is_caged: false,
+        final_price: 0,
+        settled_collateral: 0,
+        settlement_shortfall: 0,
+        protocol_liquidation_share: 0,
+        liquidation_penalty_bps: 500,
+        close_factor_bps: DEFAULT_CLOSE_FACTOR_BPS,
+        debt_dust: DEFAULT_DEBT_DUST,
+        auction: AuctionConfig::default(),
+        auction_collateral: 0,
+        redemption_fee_bps: 0,
+    }
+}
+
+/// An empty stability pool
+pub fn stability_pool(pool: Pubkey) -> StabilityPool {
+    StabilityPool {
+        pool,
+        total_deposits: 0,
+        open_deposits: 0,
+        p: WAD,
+        current_scale: 0,
+        scale_sums: vec![0],
+        bump: 0,
+    }
+}
+
+/// A stability deposit that has not been snapshotted yet
+pub fn stability_deposit(owner: Pubkey, stability_pool: Pubkey) -> StabilityDeposit {
+    StabilityDeposit {
+        owner,
+        stability_pool,
+        amount: 0,
+        snapshot_p: 0,
+        snapshot_s: 0,
+        snapshot_scale: 0,
+        bump: 0,
+    }
+}
+
+/// A cross-margin account without positions or debt
+pub fn cross_margin_account(owner: Pubkey) -> CrossMarginAccount {
+    CrossMarginAccount {
+        owner,
+        positions: Vec::new(),
+        debt_amount: 0,
+        accrued_interest: 0,
+        normalized_debt: 0,
+        last_update: 0,
+        bump: 0,
+    }
+}
+
+pub fn savings(total_assets: u64, savings_rate_bps: u64) -> Savings {
+    Savings {
+        share_mint: Pubkey::default(),
+        total_assets,
+        savings_rate_bps,
+        last_accrual: 0,
+        bump: 0,
+    }
+}
+
+pub fn pool_registry(authority: Pubkey, pools: Vec<Pubkey>) -> PoolRegistry {
+    PoolRegistry {
+        authority,
+        pools,
+        bump: Pubkey::find_program_address(&[SEED_POOL_REGISTRY], &crate::ID).1,
+    }
+}
+
+/// A PSM for `mint` with nothing swapped in yet
+pub fn psm(mint: Pubkey, tin_bps: u64, tout_bps: u64, debt_ceiling: u64) -> Psm {
+    Psm {
+        mint,
+        tin_bps,
+        tout_bps,
+        debt_ceiling,
+        debt: 0,
+        bump: Pubkey::find_program_address(&[SEED_PSM, mint.as_ref()], &crate::ID).1,
+    }
+}
@@ -0,0 +1,268 @@
+use anchor_lang::prelude::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::math::*;
+
+/// Convert normalized debt into owed debt at `rate_index`, rounding up
+pub fn normalized_to_debt(normalized_debt: u128, rate_index: u128) -> Result<u64> {
+    let debt = normalized_debt
+        .checked_mul(rate_index)
+        .ok_or(StableError::Overflow)?
+        .div_ceil(WAD);
+
+    u64::try_from(debt).map_err(|_| StableError::Overflow.into())
+}
+
+/// Convert owed debt into normalized debt at `rate_index`
+pub fn debt_to_normalized(debt: u64, rate_index: u128, round_up: bool) -> Result<u128> {
+    require!(rate_index > 0, StableError::InvalidParameter);
+
+    let numerator = (debt as u128)
+        .checked_mul(WAD)
+        .ok_or(StableError::Overflow)?;
+
+    Ok(if round_up {
+        numerator.div_ceil(rate_index)
+    } else {
+        numerator / rate_index
+    })
+}
+
+/// Total debt owed to a pool, including interest, at its current rate index
+pub fn pool_debt(pool: &CollateralPool) -> Result<u64> {
+    normalized_to_debt(pool.total_normalized_debt, pool.rate_index)
+}
+
+/// Roll the pool's cumulative rate index forward to `current_time`.
+/// Interest accrued by the whole pool is added to `GlobalState::total_debt`.
+/// Caged pools no longer accrue.
+pub fn accrue_pool_interest(
+    pool: &mut CollateralPool,
+    global_state: &mut GlobalState,
+    current_time: i64,
+) -> Result<()> {
+    if pool.is_caged || current_time <= pool.last_accrual {
+        return Ok(());
+    }
+
+    let factor = compound_factor(global_state.stability_fee, current_time - pool.last_accrual)?;
+    let new_rate_index = wad_mul(pool.rate_index, factor)?;
+
+    let debt_before = pool_debt(pool)?;
+    pool.rate_index = new_rate_index;
+    pool.last_accrual = current_time;
+    let debt_after = pool_debt(pool)?;
+
+    global_state.total_debt = global_state.total_debt
+        .checked_add(debt_after.saturating_sub(debt_before))
+        .ok_or(StableError::Overflow)?;
+
+    Ok(())
+}
+
+/// Debt owed by a vault at the pool's current rate index.
+/// Refreshes `accrued_interest` so it reflects interest owed on top of principal.
+pub fn sync_vault_debt(user_vault: &mut UserVault, pool: &CollateralPool) -> Result<u64> {
+    let owed = normalized_to_debt(user_vault.normalized_debt as u128, pool.rate_index)?;
+    user_vault.accrued_interest = owed.saturating_sub(user_vault.debt_amount);
+    Ok(owed)
+}
+
+/// Add newly minted principal to a vault, its pool and the global total
+pub fn add_vault_debt(
+    user_vault: &mut UserVault,
+    pool: &mut CollateralPool,
+    global_state: &mut GlobalState,
+    amount: u64,
+) -> Result<()> {
+    let normalized = debt_to_normalized(amount, pool.rate_index, true)?;
+    let normalized_u64 = u64::try_from(normalized).map_err(|_| StableError::Overflow)?;
+
+    user_vault.normalized_debt = user_vault.normalized_debt
+        .checked_add(normalized_u64)
+        .ok_or(StableError::Overflow)?;
+    user_vault.debt_amount = user_vault.debt_amount
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    pool.total_normalized_debt = pool.total_normalized_debt
+        .checked_add(normalized)
+        .ok_or(StableError::Overflow)?;
+
+    global_state.total_debt = global_state.total_debt
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    sync_vault_debt(user_vault, pool)?;
+    Ok(())
+}
+
+/// Pay down `amount` of a vault's debt, interest first then principal.
+/// Returns (principal_paid, interest_paid).
+pub fn repay_vault_debt(
+    user_vault: &mut UserVault,
+    pool: &mut CollateralPool,
+    global_state: &mut GlobalState,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let owed = sync_vault_debt(user_vault, pool)?;
+    require!(amount <= owed, StableError::RepayAmountExceedsDebt);
+
+    let interest_paid = amount.min(user_vault.accrued_interest);
+    let principal_paid = amount
+        .checked_sub(interest_paid)
+        .ok_or(StableError::Overflow)?
+        .min(user_vault.debt_amount);
+
+    // a full repayment clears the vault exactly, partial ones round in favour of the pool
+    let normalized_repaid = if amount == owed {
+        user_vault.normalized_debt as u128
+    } else {
+        debt_to_normalized(amount, pool.rate_index, false)?
+            .min(user_vault.normalized_debt as u128)
+    };
+
+    user_vault.normalized_debt = user_vault.normalized_debt
+        .checked_sub(normalized_repaid as u64)
+        .ok_or(StableError::Overflow)?;
+    user_vault.debt_amount = user_vault.debt_amount
+        .checked_sub(principal_paid)
+        .ok_or(StableError::Overflow)?;
+
+    pool.total_normalized_debt = pool.total_normalized_debt
+        .saturating_sub(normalized_repaid);
+
+    // vault debts round up while the pool total rounds once, so the global total may lag by dust
+    global_state.total_debt = global_state.total_debt.saturating_sub(amount);
+
+    if user_vault.normalized_debt == 0 {
+        user_vault.debt_amount = 0;
+    }
+    sync_vault_debt(user_vault, pool)?;
+
+    Ok((principal_paid, interest_paid))
+}
+
+/// Roll the cross-margin rate index forward to `current_time` at the stability fee.
+/// Interest accrued by all cross-margin debt is added to `GlobalState::total_debt`.
+/// Stops accruing once the system is shut down.
+pub fn accrue_cross_margin_interest(global_state: &mut GlobalState, current_time: i64) -> Result<()> {
+    if global_state.is_shutdown || current_time <= global_state.cross_last_accrual {
+        return Ok(());
+    }
+
+    let factor = compound_factor(global_state.stability_fee, current_time - global_state.cross_last_accrual)?;
+    let new_rate_index = wad_mul(global_state.cross_rate_index, factor)?;
+
+    let debt_before = normalized_to_debt(global_state.cross_normalized_debt, global_state.cross_rate_index)?;
+    global_state.cross_rate_index = new_rate_index;
+    global_state.cross_last_accrual = current_time;
+    let debt_after = normalized_to_debt(global_state.cross_normalized_debt, global_state.cross_rate_index)?;
+
+    global_state.total_debt = global_state.total_debt
+        .checked_add(debt_after.saturating_sub(debt_before))
+        .ok_or(StableError::Overflow)?;
+
+    Ok(())
+}
+
+/// Debt owed by a cross-margin account at the current cross rate index.
+/// Refreshes `accrued_interest` so it reflects interest owed on top of principal.
+pub fn sync_cross_margin_debt(account: &mut CrossMarginAccount, global_state: &GlobalState) -> Result<u64> {
+    let owed = normalized_to_debt(account.normalized_debt as u128, global_state.cross_rate_index)?;
+    account.accrued_interest = owed.saturating_sub(account.debt_amount);
+    Ok(owed)
+}
+
+/// Add newly minted principal to a cross-margin account and the global totals
+pub fn add_cross_margin_debt(
+    account: &mut CrossMarginAccount,
+    global_state: &mut GlobalState,
+    amount: u64,
+) -> Result<()> {
+    let normalized = debt_to_normalized(amount, global_state.cross_rate_index, true)?;
+    let normalized_u64 = u64::try_from(normalized).map_err(|_| StableError::Overflow)?;
+
+    account.normalized_debt = account.normalized_debt
+        .checked_add(normalized_u64)
+        .ok_or(StableError::Overflow)?;
+    account.debt_amount = account.debt_amount
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    global_state.cross_normalized_debt = global_state.cross_normalized_debt
+        .checked_add(normalized)
+        .ok_or(StableError::Overflow)?;
+    global_state.total_debt = global_state.total_debt
+        .checked_add(amount)
+        .ok_or(StableError::Overflow)?;
+
+    sync_cross_margin_debt(account, global_state)?;
+    Ok(())
+}
+
+/// Pay down `amount` of a cross-margin account's debt, interest first then principal.
+/// Returns (principal_paid, interest_paid).
+pub fn repay_cross_margin_debt(
+    account: &mut CrossMarginAccount,
+    global_state: &mut GlobalState,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let owed = sync_cross_margin_debt(account, global_state)?;
+    require!(amount <= owed, StableError::RepayAmountExceedsDebt);
+
+    let interest_paid = amount.min(account.accrued_interest);
+    let principal_paid = amount
+        .checked_sub(interest_paid)
+        .ok_or(StableError::Overflow)?
+        .min(account.debt_amount);
+
+    let normalized_repaid = if amount == owed {
+        account.normalized_debt as u128
+    } else {
+        debt_to_normalized(amount, global_state.cross_rate_index, false)?
+            .min(account.normalized_debt as u128)
+    };
+
+    account.normalized_debt = account.normalized_debt
+        .checked_sub(normalized_repaid as u64)
+        .ok_or(StableError::Overflow)?;
+    account.debt_amount = account.debt_amount
+        .checked_sub(principal_paid)
+        .ok_or(StableError::Overflow)?;
+
+    global_state.cross_normalized_debt = global_state.cross_normalized_debt
+        .saturating_sub(normalized_repaid);
+    global_state.total_debt = global_state.total_debt.saturating_sub(amount);
+
+    if account.normalized_debt == 0 {
+        account.debt_amount = 0;
+    }
+    sync_cross_margin_debt(account, global_state)?;
+
+    Ok((principal_paid, interest_paid))
+}
+
+/// Accrue every registered pool, passed as writable `remaining_accounts` in registry order.
+/// Used before changes that affect all pools at once, such as a new stability fee.
+pub fn accrue_all_pools<'info>(
+    pool_accounts: &'info [AccountInfo<'info>],
+    pool_registry: &PoolRegistry,
+    global_state: &mut GlobalState,
+    current_time: i64,
+) -> Result<()> {
+    require!(
+        pool_accounts.len() == pool_registry.pools.len(),
+        StableError::InvalidPool
+    );
+
+    for (pool_info, pool_key) in pool_accounts.iter().zip(pool_registry.pools.iter()) {
+        require_keys_eq!(pool_info.key(), *pool_key, StableError::InvalidPool);
+
+        let mut pool = Account::<CollateralPool>::try_from(pool_info)?;
+        accrue_pool_interest(&mut pool, global_state, current_time)?;
+        pool.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
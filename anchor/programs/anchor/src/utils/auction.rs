@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::math::*;
+
+/// Sanity checks for an auction config before it is stored on a pool
+pub fn validate_auction_config(config: &AuctionConfig) -> Result<()> {
+    require!(config.buf_bps >= BASIS_POINTS_DIVISOR, StableError::InvalidParameter);
+    require!(config.duration > 0, StableError::InvalidParameter);
+
+    match config.curve {
+        AUCTION_CURVE_LINEAR => {}
+        AUCTION_CURVE_STAIRSTEP => {
+            require!(config.step > 0, StableError::InvalidParameter);
+            require!(
+                config.cut_bps > 0 && config.cut_bps < BASIS_POINTS_DIVISOR,
+                StableError::InvalidParameter
+            );
+        }
+        _ => return err!(StableError::InvalidParameter),
+    }
+
+    Ok(())
+}
+
+/// Opening price of an auction: oracle price scaled up by `buf_bps`
+pub fn auction_start_price(config: &AuctionConfig, oracle_price: i64) -> Result<i64> {
+    let price = (oracle_price as u128)
+        .checked_mul(config.buf_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?;
+
+    i64::try_from(price).map_err(|_| StableError::Overflow.into())
+}
+
+/// Current auction price (8 decimals) `elapsed` seconds after the start.
+/// Linear decays to zero over `duration`, stairstep multiplies by `cut_bps` every `step` seconds.
+/// Zero once `duration` has passed, the auction then has to be reset.
+pub fn auction_price(config: &AuctionConfig, start_price: i64, elapsed: i64) -> Result<i64> {
+    let elapsed = elapsed.max(0) as u64;
+    if elapsed >= config.duration {
+        return Ok(0);
+    }
+
+    let price = match config.curve {
+        AUCTION_CURVE_LINEAR => (start_price as u128)
+            .checked_mul((config.duration - elapsed) as u128)
+            .ok_or(StableError::Overflow)?
+            / config.duration as u128,
+        AUCTION_CURVE_STAIRSTEP => {
+            let cut = (config.cut_bps as u128) * WAD / (BASIS_POINTS_DIVISOR as u128);
+            let factor = wad_pow(cut, elapsed / config.step)?;
+            (start_price as u128)
+                .checked_mul(factor)
+                .ok_or(StableError::Overflow)?
+                / WAD
+        }
+        _ => return err!(StableError::InvalidParameter),
+    };
+
+    i64::try_from(price).map_err(|_| StableError::Overflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear() -> AuctionConfig {
+        AuctionConfig {
+            curve: AUCTION_CURVE_LINEAR,
+            buf_bps: 12_000,
+            duration: 1_000,
+            step: 0,
+            cut_bps: 0,
+        }
+    }
+
+    fn stairstep() -> AuctionConfig {
+        AuctionConfig {
+            curve: AUCTION_CURVE_STAIRSTEP,
+            buf_bps: 12_000,
+            duration: 3_600,
+            step: 60,
+            cut_bps: 9_900,
+        }
+    }
+
+    #[test]
+    fn linear_price_decays_to_zero() {
+        let config = linear();
+        assert_eq!(auction_price(&config, 100_000_000, 0).unwrap(), 100_000_000);
+        assert_eq!(auction_price(&config, 100_000_000, 250).unwrap(), 75_000_000);
+        assert_eq!(auction_price(&config, 100_000_000, 999).unwrap(), 100_000);
+        assert_eq!(auction_price(&config, 100_000_000, 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn stairstep_price_cuts_every_step() {
+        let config = stairstep();
+        assert_eq!(auction_price(&config, 100_000_000, 59).unwrap(), 100_000_000);
+        assert_eq!(auction_price(&config, 100_000_000, 60).unwrap(), 99_000_000);
+        assert_eq!(auction_price(&config, 100_000_000, 120).unwrap(), 98_010_000);
+        assert_eq!(auction_price(&config, 100_000_000, 3_600).unwrap(), 0);
+    }
+
+    #[test]
+    fn start_price_applies_buffer() {
+        assert_eq!(auction_start_price(&linear(), 100_000_000).unwrap(), 120_000_000);
+        assert!(validate_auction_config(&linear()).is_ok());
+        assert!(validate_auction_config(&stairstep()).is_ok());
+        assert!(validate_auction_config(&AuctionConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::math::*;
+use crate::utils::oracle::load_collateral_price;
+
+/// Debt a cross-margin account can carry, summed over its positions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrossMarginHealth {
+    /// Max debt for mints and withdrawals: value at the low price end over `collateral_factor`.
+    /// Inactive and reduce-only pools do not count.
+    pub borrow_limit: u64,
+    /// Debt above this is liquidatable: value at the oracle price over `liquidation_factor`
+    pub liquidation_limit: u64,
+    /// Smallest debt the account may carry: the largest `debt_dust` of its pools
+    pub min_debt: u64,
+}
+
+/// Debt that `value` can back at a minimum collateral ratio of `factor_bps`
+pub fn weighted_collateral_value(value: u64, factor_bps: u64) -> Result<u64> {
+    require!(factor_bps > 0, StableError::InvalidParameter);
+
+    let weighted = (value as u128)
+        .checked_mul(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?
+        / factor_bps as u128;
+
+    u64::try_from(weighted).map_err(|_| StableError::Overflow.into())
+}
+
+impl CrossMarginAccount {
+    pub fn position(&self, pool: &Pubkey) -> Option<&CrossMarginPosition> {
+        self.positions.iter().find(|position| position.pool == *pool)
+    }
+
+    /// Add shares to the account's position in `pool`, opening it if needed
+    pub fn add_shares(&mut self, pool: Pubkey, decimals: u8, shares: u64) -> Result<()> {
+        if let Some(position) = self.positions.iter_mut().find(|position| position.pool == pool) {
+            position.collateral_shares = position.collateral_shares
+                .checked_add(shares)
+                .ok_or(StableError::Overflow)?;
+            return Ok(());
+        }
+
+        require!(
+            self.positions.len() < MAX_CROSS_MARGIN_POSITIONS,
+            StableError::MaxCrossMarginPositionsReached
+        );
+        self.positions.push(CrossMarginPosition {
+            pool,
+            collateral_shares: shares,
+            decimals,
+        });
+        Ok(())
+    }
+
+    /// Remove shares from the position in `pool`, dropping the position once it is empty
+    pub fn remove_shares(&mut self, pool: &Pubkey, shares: u64) -> Result<()> {
+        let index = self.positions
+            .iter()
+            .position(|position| position.pool == *pool)
+            .ok_or(StableError::InvalidPool)?;
+
+        let position = &mut self.positions[index];
+        position.collateral_shares = position.collateral_shares
+            .checked_sub(shares)
+            .ok_or(StableError::InsufficientShares)?;
+        if position.collateral_shares == 0 {
+            self.positions.remove(index);
+        }
+        Ok(())
+    }
+}
+
+/// Smallest debt a cross-margin account may carry: the largest `debt_dust` of its pools.
+/// `pool_accounts` holds the pool of every position, in position order.
+pub fn cross_margin_min_debt<'info>(
+    account: &CrossMarginAccount,
+    pool_accounts: &'info [AccountInfo<'info>],
+) -> Result<u64> {
+    require!(pool_accounts.len() == account.positions.len(), StableError::InvalidPool);
+
+    let mut min_debt = 0;
+    for (position, pool_info) in account.positions.iter().zip(pool_accounts.iter()) {
+        require_keys_eq!(pool_info.key(), position.pool, StableError::InvalidPool);
+        let pool = Account::<CollateralPool>::try_from(pool_info)?;
+        min_debt = pool.debt_dust.max(min_debt);
+    }
+
+    Ok(min_debt)
+}
+
+/// Health of a cross-margin account.
+/// `oracle_accounts` holds one pair per position, in position order: [pool, price_feed].
+/// `updated_pool` is a pool the instruction has already modified in memory, used
+/// instead of its (stale) remaining account.
+pub fn cross_margin_health<'info>(
+    account: &CrossMarginAccount,
+    oracle_accounts: &'info [AccountInfo<'info>],
+    updated_pool: Option<(Pubkey, &CollateralPool)>,
+) -> Result<CrossMarginHealth> {
+    require!(
+        oracle_accounts.len() == account.positions.len() * 2,
+        StableError::InvalidPool
+    );
+
+    let mut health = CrossMarginHealth::default();
+    for (position, accounts) in account.positions.iter().zip(oracle_accounts.chunks(2)) {
+        require_keys_eq!(accounts[0].key(), position.pool, StableError::InvalidPool);
+
+        let loaded_pool;
+        let pool = match updated_pool {
+            Some((key, pool)) if key == position.pool => pool,
+            _ => {
+                loaded_pool = Account::<CollateralPool>::try_from(&accounts[0])?;
+                &*loaded_pool
+            }
+        };
+
+        let collateral_amount = if pool.total_shares == 0 {
+            0
+        } else {
+            calculate_amount_from_shares(position.collateral_shares, pool.total_collateral, pool.total_shares)?
+        };
+        let oracle_price = load_collateral_price(&accounts[1], pool)?;
+
+        if pool.is_active && !pool.reduce_only {
+            let value = calculate_collateral_value(collateral_amount, oracle_price.min_price()?, position.decimals)?;
+            health.borrow_limit = health.borrow_limit
+                .checked_add(weighted_collateral_value(value, pool.collateral_factor)?)
+                .ok_or(StableError::Overflow)?;
+        }
+
+        let value = calculate_collateral_value(collateral_amount, oracle_price.price, position.decimals)?;
+        health.liquidation_limit = health.liquidation_limit
+            .checked_add(weighted_collateral_value(value, pool.liquidation_factor)?)
+            .ok_or(StableError::Overflow)?;
+        health.min_debt = health.min_debt.max(pool.debt_dust);
+    }
+
+    Ok(health)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn weights_value_by_the_collateral_ratio() {
+        assert_eq!(weighted_collateral_value(1_500_000, 15_000).unwrap(), 1_000_000);
+        assert_eq!(weighted_collateral_value(1_000_000, 12_500).unwrap(), 800_000);
+        assert!(weighted_collateral_value(1_000_000, 0).is_err());
+    }
+
+    #[test]
+    fn positions_open_and_close_with_their_shares() {
+        let mut account = test_utils::cross_margin_account(Pubkey::default());
+        let pools: Vec<Pubkey> = (0..=MAX_CROSS_MARGIN_POSITIONS).map(|_| Pubkey::new_unique()).collect();
+
+        account.add_shares(pools[0], 9, 100).unwrap();
+        account.add_shares(pools[0], 9, 50).unwrap();
+        assert_eq!(account.positions.len(), 1);
+        assert_eq!(account.position(&pools[0]).unwrap().collateral_shares, 150);
+
+        for pool in &pools[1..MAX_CROSS_MARGIN_POSITIONS] {
+            account.add_shares(*pool, 6, 1).unwrap();
+        }
+        assert!(account.add_shares(pools[MAX_CROSS_MARGIN_POSITIONS], 6, 1).is_err());
+
+        assert!(account.remove_shares(&pools[0], 151).is_err());
+        account.remove_shares(&pools[0], 150).unwrap();
+        assert!(account.position(&pools[0]).is_none());
+        account.add_shares(pools[MAX_CROSS_MARGIN_POSITIONS], 6, 1).unwrap();
+    }
+
+    #[test]
+    fn min_debt_is_the_largest_pool_dust() {
+        let mut account = test_utils::cross_margin_account(Pubkey::default());
+        let (pool_a, pool_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        account.add_shares(pool_a, 9, 1).unwrap();
+        account.add_shares(pool_b, 6, 1).unwrap();
+
+        let mut state_a = test_utils::collateral_pool(Pubkey::new_unique(), [1; 32]);
+        state_a.debt_dust = 10_000_000;
+        let mut state_b = test_utils::collateral_pool(Pubkey::new_unique(), [2; 32]);
+        state_b.debt_dust = 50_000_000;
+        let mut a = test_utils::TestAccount::state(pool_a, &state_a);
+        let mut b = test_utils::TestAccount::state(pool_b, &state_b);
+
+        let infos = [a.info(), b.info()];
+        assert_eq!(cross_margin_min_debt(&account, &infos).unwrap(), 50_000_000);
+        assert!(cross_margin_min_debt(&account, &infos[..1]).is_err());
+
+        let swapped = [infos[1].clone(), infos[0].clone()];
+        assert!(cross_margin_min_debt(&account, &swapped).is_err());
+    }
+}
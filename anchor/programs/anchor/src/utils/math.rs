@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
 use crate::errors::StableError;
 
 pub fn calculate_shares_from_amount(
@@ -52,4 +53,211 @@ pub fn calculate_collateralization_ratio(
         .ok_or(StableError::Overflow)?;
     
     Ok(ratio)
-}
\ No newline at end of file
+}
+
+/// Value of `collateral_amount` in stablecoin base units.
+/// `price` is normalized to 8 decimals, the result uses the 6 WUSD decimals.
+pub fn calculate_collateral_value(
+    collateral_amount: u64,
+    price: i64,
+    collateral_decimals: u8,
+) -> Result<u64> {
+    require!(price > 0, StableError::InvalidOracle);
+
+    let value = (collateral_amount as u128)
+        .checked_mul(price as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(10u128.pow(collateral_decimals as u32))
+        .ok_or(StableError::Overflow)?
+        .checked_div(10u128.pow(PRICE_DECIMALS - STABLE_DECIMALS))
+        .ok_or(StableError::Overflow)?;
+
+    u64::try_from(value).map_err(|_| StableError::Overflow.into())
+}
+
+/// Amount of collateral tokens worth `value` stablecoin base units at `price` (8 decimals).
+/// Inverse of `calculate_collateral_value`.
+pub fn calculate_collateral_amount(
+    value: u64,
+    price: i64,
+    collateral_decimals: u8,
+) -> Result<u64> {
+    require!(price > 0, StableError::InvalidOracle);
+
+    let amount = (value as u128)
+        .checked_mul(10u128.pow(collateral_decimals as u32))
+        .ok_or(StableError::Overflow)?
+        .checked_mul(10u128.pow(PRICE_DECIMALS - STABLE_DECIMALS))
+        .ok_or(StableError::Overflow)?
+        .checked_div(price as u128)
+        .ok_or(StableError::Overflow)?;
+
+    u64::try_from(amount).map_err(|_| StableError::Overflow.into())
+}
+
+/// Collateral ratio in basis points, computed in u128 to avoid overflow
+pub fn calculate_collateral_ratio_bps(
+    collateral_value: u64,
+    debt_amount: u64,
+) -> Result<u64> {
+    let ratio = (collateral_value as u128)
+        .checked_mul(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div((debt_amount as u128).max(1))
+        .ok_or(StableError::Overflow)?;
+
+    Ok(ratio.min(u64::MAX as u128) as u64)
+}
+
+/// Fixed-point scale for rate math (1e18)
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// Multiply two WAD values, rounding down.
+/// The larger operand is split into whole and fractional parts so the
+/// product only overflows when the smaller operand exceeds ~340 WAD.
+pub fn wad_mul(a: u128, b: u128) -> Result<u128> {
+    let (large, small) = if a >= b { (a, b) } else { (b, a) };
+
+    let whole = (large / WAD)
+        .checked_mul(small)
+        .ok_or(StableError::Overflow)?;
+    let fraction = (large % WAD)
+        .checked_mul(small)
+        .ok_or(StableError::Overflow)?
+        / WAD;
+
+    Ok(whole
+        .checked_add(fraction)
+        .ok_or(StableError::Overflow)?)
+}
+
+/// Raise a WAD value to an integer power by repeated squaring
+pub fn wad_pow(base: u128, exp: u64) -> Result<u128> {
+    let mut result = WAD;
+    let mut base = base;
+    let mut exp = exp;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = wad_mul(result, base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = wad_mul(base, base)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Per-second growth factor (WAD) for an annual rate in basis points
+pub fn per_second_rate(annual_rate_bps: u64) -> u128 {
+    WAD + (annual_rate_bps as u128) * WAD
+        / (BASIS_POINTS_DIVISOR as u128)
+        / (SECONDS_PER_YEAR as u128)
+}
+
+/// Growth factor (WAD) of debt compounded every second for `time_elapsed` seconds
+pub fn compound_factor(annual_rate_bps: u64, time_elapsed: i64) -> Result<u128> {
+    if time_elapsed <= 0 || annual_rate_bps == 0 {
+        return Ok(WAD);
+    }
+
+    wad_pow(per_second_rate(annual_rate_bps), time_elapsed as u64)
+}
+
+/// Interest accrued on `debt_amount` over `time_elapsed` seconds at `stability_fee_bps` APR,
+/// compounded per second. Rounded down.
+pub fn calculate_interest(
+    debt_amount: u64,
+    stability_fee_bps: u64,
+    time_elapsed: i64,
+) -> Result<u64> {
+    if debt_amount == 0 {
+        return Ok(0);
+    }
+
+    let factor = compound_factor(stability_fee_bps, time_elapsed)?;
+    let interest = (debt_amount as u128)
+        .checked_mul(factor - WAD)
+        .ok_or(StableError::Overflow)?
+        / WAD;
+
+    u64::try_from(interest).map_err(|_| StableError::Overflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference curve: (1 + r / SECONDS_PER_YEAR) ^ t, evaluated as exp(t * ln(1 + r / SECONDS_PER_YEAR))
+    fn reference_factor(annual_rate_bps: u64, seconds: u64) -> f64 {
+        let per_second = (annual_rate_bps as f64)
+            / (BASIS_POINTS_DIVISOR as f64)
+            / (SECONDS_PER_YEAR as f64);
+        ((seconds as f64) * per_second.ln_1p()).exp()
+    }
+
+    fn to_f64(wad: u128) -> f64 {
+        wad as f64 / WAD as f64
+    }
+
+    // Fixed-point result must stay within 1e-9 relative error of the reference curve
+    const TOLERANCE: f64 = 1e-9;
+
+    #[test]
+    fn compound_factor_is_one_without_time_or_rate() {
+        assert_eq!(compound_factor(500, 0).unwrap(), WAD);
+        assert_eq!(compound_factor(500, -10).unwrap(), WAD);
+        assert_eq!(compound_factor(0, 86_400).unwrap(), WAD);
+    }
+
+    #[test]
+    fn compound_factor_matches_reference_curve() {
+        let rates = [1, 50, 500, 2_500, MAX_STABILITY_FEE_BPS];
+        let periods = [1, 60, 3_600, 86_400, 30 * 86_400, SECONDS_PER_YEAR, 3 * SECONDS_PER_YEAR];
+
+        for &rate in &rates {
+            for &seconds in &periods {
+                let actual = to_f64(compound_factor(rate, seconds as i64).unwrap());
+                let expected = reference_factor(rate, seconds);
+                let error = ((actual - expected) / expected).abs();
+                assert!(
+                    error < TOLERANCE,
+                    "rate {} bps over {} s: got {}, expected {}",
+                    rate, seconds, actual, expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compounding_is_consistent_across_splits() {
+        let whole = compound_factor(500, 2 * 86_400).unwrap();
+        let halves = wad_mul(
+            compound_factor(500, 86_400).unwrap(),
+            compound_factor(500, 86_400).unwrap(),
+        )
+        .unwrap();
+        let error = (to_f64(whole) - to_f64(halves)).abs() / to_f64(whole);
+        assert!(error < TOLERANCE);
+    }
+
+    #[test]
+    fn one_year_of_interest_compounds() {
+        // 1,000 WUSD at 5% APR, compounded per second ~= 1,000 * (e^0.05 - 1) = 51.27 WUSD
+        let debt = 1_000_000_000u64;
+        let interest = calculate_interest(debt, 500, SECONDS_PER_YEAR as i64).unwrap();
+        let expected = (debt as f64) * (reference_factor(500, SECONDS_PER_YEAR) - 1.0);
+
+        assert!(interest > debt / 20, "compound interest must exceed simple interest");
+        assert!(((interest as f64) - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn interest_handles_large_debts() {
+        let interest = calculate_interest(u64::MAX / 4, 500, 86_400).unwrap();
+        let expected = ((u64::MAX / 4) as f64) * (reference_factor(500, 86_400) - 1.0);
+        assert!(((interest as f64) - expected).abs() / expected < TOLERANCE);
+    }
+}
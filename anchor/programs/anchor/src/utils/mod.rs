@@ -2,4 +2,17 @@ pub use math::*;
 pub mod math;
 
 pub use oracle::*;
-pub mod oracle;
\ No newline at end of file
+pub mod oracle;
+
+pub use accrual::*;
+pub mod accrual;
+pub use auction::*;
+pub mod auction;
+pub use stability_pool::*;
+pub mod stability_pool;
+pub use sorted_vaults::*;
+pub mod sorted_vaults;
+pub use savings::*;
+pub mod savings;
+pub use cross_margin::*;
+pub mod cross_margin;
\ No newline at end of file
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
 use crate::errors::*;
+use crate::states::*;
+use pyth_solana_receiver_sdk::error::GetPriceError;
 use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 // Pyth Feed IDs from https://pyth.network/developers/price-feed-ids
@@ -11,58 +14,260 @@ pub const SUI_USD_FEED_ID: &str = "0x23d7315113f5b1d3ba7a83604c44b94d79f4fd69af7
 
 pub const MAXIMUM_AGE: u64 = 60 * 5; // 5 minutes staleness
 
-/// Parse price from our MockPriceFeed account (per-collateral)
-/// Layout: [8 discriminator][32 collateral_mint][8 price][8 confidence][8 last_update][1 bump]
-/// Returns (price, confidence) both normalized to 8 decimal places
-pub fn parse_pyth_price(account_data: &[u8]) -> Result<(i64, u64)> {
-    if account_data.len() < 57 {
-        return Err(StableError::InvalidOracle.into());
+/// Decode a hex Pyth feed id (e.g. `SOL_USD_FEED_ID`) into the bytes stored in `OracleConfig::feed_id`
+pub fn feed_id_from_hex(feed_id_hex: &str) -> Result<[u8; 32]> {
+    get_feed_id_from_hex(feed_id_hex).map_err(|_| StableError::InvalidOracle.into())
+}
+
+/// Sanity checks for an oracle config before it is stored on a pool
+pub fn validate_oracle_config(oracle: &OracleConfig) -> Result<()> {
+    require!(oracle.max_staleness > 0, StableError::InvalidParameter);
+    require!(
+        oracle.max_confidence_bps > 0 && oracle.max_confidence_bps <= BASIS_POINTS_DIVISOR,
+        StableError::InvalidParameter
+    );
+    Ok(())
+}
+
+/// Oracle price normalized to 8 decimal places
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub confidence: u64,
+}
+
+impl OraclePrice {
+    /// Lower bound of the confidence interval (price - conf).
+    /// Used to value collateral that backs debt (mint, withdraw).
+    pub fn min_price(&self) -> Result<i64> {
+        let confidence = i64::try_from(self.confidence).map_err(|_| StableError::Overflow)?;
+        let price = self.price
+            .checked_sub(confidence)
+            .ok_or(StableError::Overflow)?;
+        require!(price > 0, StableError::OracleConfidenceLow);
+        Ok(price)
     }
-    
-    // Check if this is our MockPriceFeed account (has Anchor discriminator)
-    let mock_discriminator: [u8; 8] = [73, 0, 218, 41, 7, 202, 200, 152];
-    
-    if account_data[..8] == mock_discriminator {
-        // This is our MockPriceFeed PDA
-        // Skip discriminator (8) + collateral_mint (32) = 40 bytes
-        let price_bytes = &account_data[40..48];
-        let price = i64::from_le_bytes(price_bytes.try_into().unwrap());
-        
-        let conf_bytes = &account_data[48..56];
-        let conf = u64::from_le_bytes(conf_bytes.try_into().unwrap());
-        
-        return Ok((price, conf));
+
+    /// Upper bound of the confidence interval (price + conf).
+    /// Used when converting stablecoin into collateral paid out of the protocol.
+    pub fn max_price(&self) -> Result<i64> {
+        let confidence = i64::try_from(self.confidence).map_err(|_| StableError::Overflow)?;
+        Ok(self.price
+            .checked_add(confidence)
+            .ok_or(StableError::Overflow)?)
     }
-    
-    // Not a mock feed - return error, let the caller use PriceUpdateV2 directly
-    Err(StableError::InvalidOracle.into())
 }
 
-/// Parse price directly from PriceUpdateV2 account using Pyth SDK
-pub fn get_pyth_price(price_update: &Account<PriceUpdateV2>, clock: &Clock) -> Result<(i64, u64)> {
-    let feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
+/// Reject non-positive prices and confidence intervals wider than the pool allows
+fn check_confidence(oracle_price: &OraclePrice, oracle: &OracleConfig) -> Result<()> {
+    require!(oracle_price.price > 0, StableError::InvalidOracle);
+
+    let max_confidence = (oracle_price.price as u128)
+        .checked_mul(oracle.max_confidence_bps as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(StableError::Overflow)?;
+    require!(
+        (oracle_price.confidence as u128) <= max_confidence,
+        StableError::OracleConfidenceLow
+    );
+
+    Ok(())
+}
+
+/// Load the price from our MockPriceFeed PDA for the pool's collateral.
+/// Only accepted when the program is built with the `mock-oracle` feature.
+pub fn load_mock_price(price_feed: &AccountInfo, pool: &CollateralPool) -> Result<OraclePrice> {
+    require!(cfg!(feature = "mock-oracle"), StableError::MockOracleDisabled);
+    require_keys_eq!(*price_feed.owner, crate::ID, StableError::InvalidOracle);
+
+    let price_data = price_feed.try_borrow_data()?;
+    let mut data_slice: &[u8] = &price_data;
+    let mock_price_feed = MockPriceFeed::try_deserialize(&mut data_slice)
         .map_err(|_| StableError::InvalidOracle)?;
-    
+
+    require_keys_eq!(mock_price_feed.collateral_mint, pool.mint, StableError::InvalidOracle);
+
+    let expected_feed = Pubkey::create_program_address(
+        &[SEED_MOCK_PRICE, pool.mint.as_ref(), &[mock_price_feed.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| StableError::InvalidOracle)?;
+    require_keys_eq!(price_feed.key(), expected_feed, StableError::InvalidOracle);
+
+    let max_staleness = i64::try_from(pool.oracle.max_staleness).map_err(|_| StableError::Overflow)?;
+    require!(
+        mock_price_feed.last_update.saturating_add(max_staleness) >= Clock::get()?.unix_timestamp,
+        StableError::OraclePriceStale
+    );
+
+    Ok(OraclePrice {
+        price: mock_price_feed.price,
+        confidence: mock_price_feed.confidence,
+    })
+}
+
+/// Resolve the collateral price of a pool from its price feed account.
+/// The account owner decides the source: our program for MockPriceFeed PDAs,
+/// the Pyth receiver program for PriceUpdateV2 accounts. Anything else is rejected.
+/// Staleness and confidence are checked against the pool's oracle config.
+pub fn load_collateral_price(price_feed: &AccountInfo, pool: &CollateralPool) -> Result<OraclePrice> {
+    let oracle = &pool.oracle;
+    if oracle.price_account != Pubkey::default() {
+        require_keys_eq!(price_feed.key(), oracle.price_account, StableError::InvalidOracle);
+    }
+
+    let oracle_price = if *price_feed.owner == crate::ID {
+        load_mock_price(price_feed, pool)?
+    } else {
+        require_keys_eq!(*price_feed.owner, pyth_solana_receiver_sdk::ID, StableError::InvalidOracle);
+
+        // PriceUpdateV2 uses Anchor's discriminator (first 8 bytes), borsh for the rest
+        let price_data = price_feed.try_borrow_data()?;
+        let mut data_slice: &[u8] = &price_data;
+        let price_update = PriceUpdateV2::try_deserialize(&mut data_slice)
+            .map_err(|_| StableError::InvalidOracle)?;
+
+        get_pyth_price(&price_update, oracle, &Clock::get()?)?
+    };
+
+    check_confidence(&oracle_price, oracle)?;
+
+    Ok(oracle_price)
+}
+
+/// Parse price directly from PriceUpdateV2 account using Pyth SDK
+pub fn get_pyth_price(
+    price_update: &PriceUpdateV2,
+    oracle: &OracleConfig,
+    clock: &Clock,
+) -> Result<OraclePrice> {
     let price = price_update
-        .get_price_no_older_than(clock, MAXIMUM_AGE, &feed_id)
-        .map_err(|_| StableError::InvalidOracle)?;
+        .get_price_no_older_than(clock, oracle.max_staleness, &oracle.feed_id)
+        .map_err(|e| match e {
+            GetPriceError::PriceTooOld => StableError::OraclePriceStale,
+            _ => StableError::InvalidOracle,
+        })?;
     
     // Normalize to 8 decimal places
     // Pyth prices typically have exponent of -8
-    let exponent = price.exponent;
-    let scale = 8 + exponent; // exponent is typically negative
+    let scale = PRICE_DECIMALS as i32 + price.exponent; // exponent is typically negative
     
-    let normalized_price = if scale >= 0 {
-        price.price.checked_mul(10i64.pow(scale as u32)).unwrap_or(price.price)
+    let (normalized_price, normalized_conf) = if scale >= 0 {
+        let factor = 10i64.checked_pow(scale as u32).ok_or(StableError::Overflow)?;
+        (
+            price.price.checked_mul(factor).ok_or(StableError::Overflow)?,
+            price.conf.checked_mul(factor as u64).ok_or(StableError::Overflow)?,
+        )
     } else {
-        price.price.checked_div(10i64.pow((-scale) as u32)).unwrap_or(price.price)
+        let factor = 10i64.checked_pow((-scale) as u32).ok_or(StableError::Overflow)?;
+        (price.price / factor, price.conf / factor as u64)
     };
     
-    let normalized_conf = if scale >= 0 {
-        price.conf.checked_mul(10u64.pow(scale as u32)).unwrap_or(price.conf)
-    } else {
-        price.conf.checked_div(10u64.pow((-scale) as u32)).unwrap_or(price.conf)
-    };
-    
-    Ok((normalized_price, normalized_conf))
-}
\ No newline at end of file
+    Ok(OraclePrice {
+        price: normalized_price,
+        confidence: normalized_conf,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, assert_error, TestAccount};
+
+    const NOW: i64 = 1_700_000_000;
+    const FEED_ID: [u8; 32] = [7; 32];
+
+    fn pool() -> CollateralPool {
+        test_utils::collateral_pool(Pubkey::new_unique(), FEED_ID)
+    }
+
+    #[test]
+    fn reads_a_fresh_pyth_price() {
+        test_utils::set_time(NOW);
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 150_00000000, 1_00000000, NOW - 10);
+
+        let price = load_collateral_price(&feed.info(), &pool()).unwrap();
+        assert_eq!(price.price, 150_00000000);
+        assert_eq!(price.confidence, 1_00000000);
+    }
+
+    #[test]
+    fn rejects_feeds_not_owned_by_the_pyth_receiver() {
+        test_utils::set_time(NOW);
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 150_00000000, 0, NOW);
+        feed.owner = Pubkey::new_unique();
+
+        assert_error(load_collateral_price(&feed.info(), &pool()), StableError::InvalidOracle);
+    }
+
+    #[test]
+    fn rejects_updates_for_another_feed() {
+        test_utils::set_time(NOW);
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), [8; 32], 150_00000000, 0, NOW);
+
+        assert_error(load_collateral_price(&feed.info(), &pool()), StableError::InvalidOracle);
+    }
+
+    #[test]
+    fn rejects_a_price_account_other_than_the_configured_one() {
+        test_utils::set_time(NOW);
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 150_00000000, 0, NOW);
+        let mut pool = pool();
+        pool.oracle.price_account = Pubkey::new_unique();
+
+        assert_error(load_collateral_price(&feed.info(), &pool), StableError::InvalidOracle);
+
+        pool.oracle.price_account = feed.key;
+        assert!(load_collateral_price(&feed.info(), &pool).is_ok());
+    }
+
+    #[test]
+    fn rejects_stale_prices() {
+        test_utils::set_time(NOW);
+        let pool = pool();
+        let max_staleness = pool.oracle.max_staleness as i64;
+
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 150_00000000, 0, NOW - max_staleness);
+        assert!(load_collateral_price(&feed.info(), &pool).is_ok());
+
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 150_00000000, 0, NOW - max_staleness - 1);
+        assert_error(load_collateral_price(&feed.info(), &pool), StableError::OraclePriceStale);
+    }
+
+    #[test]
+    fn rejects_wide_confidence_intervals() {
+        test_utils::set_time(NOW);
+        // the pool allows 2%
+        let mut feed = TestAccount::pyth_price(Pubkey::new_unique(), FEED_ID, 100_00000000, 2_00000001, NOW);
+
+        assert_error(load_collateral_price(&feed.info(), &pool()), StableError::OracleConfidenceLow);
+    }
+
+    #[test]
+    fn mock_feeds_need_the_mock_oracle_feature() {
+        test_utils::set_time(NOW);
+        let pool = pool();
+        let (key, bump) = Pubkey::find_program_address(&[SEED_MOCK_PRICE, pool.mint.as_ref()], &crate::ID);
+        let mock = |last_update| MockPriceFeed {
+            collateral_mint: pool.mint,
+            price: 150_00000000,
+            confidence: 0,
+            last_update,
+            bump,
+        };
+
+        let mut feed = TestAccount::state(key, &mock(NOW));
+        if cfg!(feature = "mock-oracle") {
+            assert_eq!(load_collateral_price(&feed.info(), &pool).unwrap().price, 150_00000000);
+
+            let mut stale = TestAccount::state(key, &mock(NOW - pool.oracle.max_staleness as i64 - 1));
+            assert_error(load_collateral_price(&stale.info(), &pool), StableError::OraclePriceStale);
+
+            let mut elsewhere = TestAccount::state(Pubkey::new_unique(), &mock(NOW));
+            assert_error(load_collateral_price(&elsewhere.info(), &pool), StableError::InvalidOracle);
+        } else {
+            assert_error(load_collateral_price(&feed.info(), &pool), StableError::MockOracleDisabled);
+        }
+    }
+}
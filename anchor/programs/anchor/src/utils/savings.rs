@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::math::*;
+
+/// Interest earned by `total_assets` over `time_elapsed` seconds at `savings_rate_bps` a year
+pub fn savings_interest(total_assets: u64, savings_rate_bps: u64, time_elapsed: i64) -> Result<u64> {
+    let factor = compound_factor(savings_rate_bps, time_elapsed)?;
+    let interest = wad_mul(total_assets as u128, factor - WAD)?;
+
+    u64::try_from(interest).map_err(|_| StableError::Overflow.into())
+}
+
+/// Roll the savings module forward to `current_time`.
+/// Interest is capped at what the treasury can fund; interest it cannot fund is forfeited.
+/// Returns the WUSD to move from the treasury into the savings account.
+pub fn accrue_savings(savings: &mut Savings, treasury_balance: u64, current_time: i64) -> Result<u64> {
+    if current_time <= savings.last_accrual {
+        return Ok(0);
+    }
+
+    let interest = savings_interest(
+        savings.total_assets,
+        savings.savings_rate_bps,
+        current_time - savings.last_accrual,
+    )?
+    .min(treasury_balance);
+
+    savings.total_assets = savings.total_assets
+        .checked_add(interest)
+        .ok_or(StableError::Overflow)?;
+    savings.last_accrual = current_time;
+
+    Ok(interest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SECONDS_PER_YEAR;
+    use crate::test_utils;
+
+    #[test]
+    fn accrues_about_the_annual_rate() {
+        let mut savings = test_utils::savings(1_000_000_000, 500);
+        let paid = accrue_savings(&mut savings, u64::MAX, SECONDS_PER_YEAR as i64).unwrap();
+
+        // 5% compounded per second is ~5.127%
+        assert!((51_200_000..51_300_000).contains(&paid));
+        assert_eq!(savings.total_assets, 1_000_000_000 + paid);
+        assert_eq!(savings.last_accrual, SECONDS_PER_YEAR as i64);
+    }
+
+    #[test]
+    fn interest_is_capped_by_the_treasury() {
+        let mut savings = test_utils::savings(1_000_000_000, 500);
+        let paid = accrue_savings(&mut savings, 1_000, SECONDS_PER_YEAR as i64).unwrap();
+
+        assert_eq!(paid, 1_000);
+        assert_eq!(savings.total_assets, 1_000_001_000);
+        assert_eq!(accrue_savings(&mut savings, u64::MAX, SECONDS_PER_YEAR as i64).unwrap(), 0);
+    }
+}
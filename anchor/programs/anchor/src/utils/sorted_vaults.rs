@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::states::*;
+
+impl SortedVaultEntry {
+    /// True when this entry has less collateral per unit of debt than `other`
+    fn is_riskier_than(&self, other: &SortedVaultEntry) -> bool {
+        (self.collateral_shares as u128) * (other.normalized_debt as u128)
+            < (other.collateral_shares as u128) * (self.normalized_debt as u128)
+    }
+}
+
+impl SortedVaults {
+    pub fn entries(&self) -> &[SortedVaultEntry] {
+        &self.entries[..self.len as usize]
+    }
+
+    pub fn position(&self, vault: &Pubkey) -> Option<usize> {
+        self.entries().iter().position(|entry| entry.vault == *vault)
+    }
+
+    pub fn remove(&mut self, vault: &Pubkey) {
+        if let Some(index) = self.position(vault) {
+            let len = self.len as usize;
+            self.entries.copy_within(index + 1..len, index);
+            self.len -= 1;
+        }
+    }
+
+    /// Re-insert a vault at its place in the order, or drop it once it has no debt
+    pub fn upsert(&mut self, vault: &Pubkey, collateral_shares: u64, normalized_debt: u64) {
+        self.remove(vault);
+        if normalized_debt == 0 {
+            return;
+        }
+
+        let entry = SortedVaultEntry {
+            vault: *vault,
+            collateral_shares,
+            normalized_debt,
+        };
+        let index = self.entries()
+            .iter()
+            .position(|other| entry.is_riskier_than(other))
+            .unwrap_or(self.len as usize);
+        if index >= MAX_SORTED_VAULTS {
+            return;
+        }
+
+        let len = self.len as usize;
+        let last = len.min(MAX_SORTED_VAULTS - 1);
+        self.entries.copy_within(index..last, index + 1);
+        self.entries[index] = entry;
+        self.len = (last + 1) as u32;
+    }
+}
+
+/// Refresh a vault's place in its pool's index after its shares or debt changed
+pub fn update_sorted_vaults(
+    sorted_vaults: &AccountLoader<SortedVaults>,
+    vault_key: &Pubkey,
+    user_vault: &UserVault,
+) -> Result<()> {
+    sorted_vaults
+        .load_mut()?
+        .upsert(vault_key, user_vault.collateral_shares, user_vault.normalized_debt);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    fn order(sorted: &SortedVaults) -> Vec<u8> {
+        sorted.entries().iter().map(|entry| entry.vault.to_bytes()[0]).collect()
+    }
+
+    #[test]
+    fn keeps_vaults_ordered_riskiest_first() {
+        let mut sorted: Box<SortedVaults> = Box::new(bytemuck::Zeroable::zeroed());
+        sorted.upsert(&key(1), 300, 100);
+        sorted.upsert(&key(2), 150, 100);
+        sorted.upsert(&key(3), 200, 100);
+        assert_eq!(order(&sorted), vec![2, 3, 1]);
+
+        // topping up vault 2 moves it to the back, repaying vault 3 removes it
+        sorted.upsert(&key(2), 1_000, 100);
+        sorted.upsert(&key(3), 200, 0);
+        assert_eq!(order(&sorted), vec![1, 2]);
+    }
+
+    #[test]
+    fn drops_the_safest_vault_when_full() {
+        let mut sorted: Box<SortedVaults> = Box::new(bytemuck::Zeroable::zeroed());
+        for n in 0..MAX_SORTED_VAULTS {
+            sorted.upsert(&Pubkey::new_unique(), 1_000 + n as u64, 100);
+        }
+        assert_eq!(sorted.len as usize, MAX_SORTED_VAULTS);
+
+        sorted.upsert(&key(7), 1, 100);
+        assert_eq!(sorted.len as usize, MAX_SORTED_VAULTS);
+        assert_eq!(sorted.entries()[0].vault, key(7));
+        assert_eq!(sorted.entries()[MAX_SORTED_VAULTS - 1].collateral_shares, 1_000 + MAX_SORTED_VAULTS as u64 - 2);
+
+        // safer than everything tracked: not inserted
+        sorted.upsert(&key(8), 10_000, 1);
+        assert_eq!(sorted.position(&key(8)), None);
+    }
+}
@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::StableError;
+use crate::states::*;
+use crate::utils::math::*;
+
+/// P is multiplied by this factor (and the scale bumped) whenever it drops below it
+pub const STABILITY_POOL_SCALE_FACTOR: u128 = 1_000_000_000;
+
+/// Debt the stability pool can absorb right now. A minimum balance is always left
+/// behind so the running product P never reaches zero.
+pub fn stability_pool_capacity(stability_pool: &StabilityPool) -> u64 {
+    stability_pool.total_deposits.saturating_sub(MIN_STABILITY_POOL_DEPOSITS)
+}
+
+/// Read a pool's stability pool from its PDA, `None` while it has not been created.
+/// The caller checks the address, only this program can create data at it.
+pub fn load_stability_pool(info: &AccountInfo) -> Result<Option<StabilityPool>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(StabilityPool::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+}
+
+/// Offset `debt` against the pool's deposits and credit `collateral` to depositors.
+/// Product-sum bookkeeping: every depositor's compounded deposit shrinks by P
+/// and their collateral gain grows by the sum S of the current scale.
+pub fn absorb_debt(stability_pool: &mut StabilityPool, debt: u64, collateral: u64) -> Result<()> {
+    require!(debt <= stability_pool_capacity(stability_pool), StableError::InvalidParameter);
+    if debt == 0 {
+        return Ok(());
+    }
+
+    let total_deposits = stability_pool.total_deposits as u128;
+
+    // S += collateral per unit deposited * P
+    let collateral_per_unit = (collateral as u128)
+        .checked_mul(WAD)
+        .ok_or(StableError::Overflow)?
+        / total_deposits;
+    let marginal_gain = wad_mul(collateral_per_unit, stability_pool.p)?;
+    let scale = stability_pool.current_scale as usize;
+    stability_pool.scale_sums[scale] = stability_pool.scale_sums[scale]
+        .checked_add(marginal_gain)
+        .ok_or(StableError::Overflow)?;
+
+    // P *= 1 - debt / deposits, rounding the loss up so deposits are never overstated
+    let loss_per_unit = (debt as u128)
+        .checked_mul(WAD)
+        .ok_or(StableError::Overflow)?
+        .div_ceil(total_deposits);
+    let mut new_p = stability_pool.p
+        .checked_mul(WAD - loss_per_unit)
+        .ok_or(StableError::Overflow)?
+        / WAD;
+
+    if new_p < STABILITY_POOL_SCALE_FACTOR {
+        require!(
+            stability_pool.scale_sums.len() < MAX_STABILITY_POOL_SCALES,
+            StableError::StabilityPoolScaleExhausted
+        );
+        new_p = new_p
+            .checked_mul(STABILITY_POOL_SCALE_FACTOR)
+            .ok_or(StableError::Overflow)?;
+        stability_pool.current_scale += 1;
+        stability_pool.scale_sums.push(0);
+    }
+    require!(new_p > 0, StableError::Overflow);
+
+    stability_pool.p = new_p;
+    stability_pool.total_deposits = stability_pool.total_deposits
+        .checked_sub(debt)
+        .ok_or(StableError::Overflow)?;
+
+    Ok(())
+}
+
+/// Let the stability pool absorb as much of a liquidation's `debt` as it can, along with the
+/// same share of the seized `collateral`. Returns the updated pool and the debt and collateral
+/// it took, `None` when it takes nothing: the pool is missing, at its minimum, or can't absorb
+/// anymore because it has run out of scales. The liquidator then covers the whole repayment.
+pub fn absorb_liquidation(
+    stability_pool: Option<StabilityPool>,
+    debt: u64,
+    collateral: u64,
+) -> Result<Option<(StabilityPool, u64, u64)>> {
+    let Some(mut stability_pool) = stability_pool else {
+        return Ok(None);
+    };
+    let absorbed_debt = debt.min(stability_pool_capacity(&stability_pool));
+    if absorbed_debt == 0 {
+        return Ok(None);
+    }
+
+    let absorbed_collateral = ((collateral as u128)
+        .checked_mul(absorbed_debt as u128)
+        .ok_or(StableError::Overflow)?
+        .checked_div(debt as u128)
+        .ok_or(StableError::Overflow)?) as u64;
+
+    Ok(absorb_debt(&mut stability_pool, absorbed_debt, absorbed_collateral)
+        .ok()
+        .map(|()| (stability_pool, absorbed_debt, absorbed_collateral)))
+}
+
+/// Current value of a deposit after the losses absorbed since its snapshot
+pub fn compounded_deposit(deposit: &StabilityDeposit, stability_pool: &StabilityPool) -> Result<u64> {
+    if deposit.amount == 0 || deposit.snapshot_p == 0 {
+        return Ok(0);
+    }
+
+    let value = (deposit.amount as u128)
+        .checked_mul(stability_pool.p)
+        .ok_or(StableError::Overflow)?
+        / deposit.snapshot_p;
+
+    let value = match stability_pool.current_scale - deposit.snapshot_scale {
+        0 => value,
+        1 => value / STABILITY_POOL_SCALE_FACTOR,
+        _ => 0,
+    };
+
+    Ok(value.min(deposit.amount as u128) as u64)
+}
+
+/// Collateral earned by a deposit since its snapshot
+pub fn collateral_gain(deposit: &StabilityDeposit, stability_pool: &StabilityPool) -> Result<u64> {
+    if deposit.amount == 0 || deposit.snapshot_p == 0 {
+        return Ok(0);
+    }
+
+    let scale = deposit.snapshot_scale as usize;
+    let first_portion = stability_pool.scale_sums[scale]
+        .checked_sub(deposit.snapshot_s)
+        .ok_or(StableError::Overflow)?;
+    let second_portion = stability_pool.scale_sums
+        .get(scale + 1)
+        .copied()
+        .unwrap_or(0)
+        / STABILITY_POOL_SCALE_FACTOR;
+
+    let gain = (deposit.amount as u128)
+        .checked_mul(first_portion + second_portion)
+        .ok_or(StableError::Overflow)?
+        / deposit.snapshot_p;
+
+    u64::try_from(gain).map_err(|_| StableError::Overflow.into())
+}
+
+/// Restart a deposit's snapshots at the pool's current P, scale and sum.
+/// Keeps the pool's count of open deposits in step.
+pub fn snapshot_deposit(deposit: &mut StabilityDeposit, stability_pool: &mut StabilityPool, amount: u64) {
+    match (deposit.amount, amount) {
+        (0, 1..) => stability_pool.open_deposits += 1,
+        (1.., 0) => stability_pool.open_deposits = stability_pool.open_deposits.saturating_sub(1),
+        _ => {}
+    }
+    deposit.amount = amount;
+    deposit.snapshot_p = stability_pool.p;
+    deposit.snapshot_scale = stability_pool.current_scale;
+    deposit.snapshot_s = stability_pool.scale_sums[stability_pool.current_scale as usize];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn deposit(stability_pool: &mut StabilityPool, amount: u64) -> StabilityDeposit {
+        let mut deposit = test_utils::stability_deposit(Pubkey::default(), Pubkey::default());
+        snapshot_deposit(&mut deposit, stability_pool, amount);
+        stability_pool.total_deposits += amount;
+        deposit
+    }
+
+    #[test]
+    fn losses_and_gains_are_pro_rata() {
+        let mut sp = test_utils::stability_pool(Pubkey::default());
+        let alice = deposit(&mut sp, 3_000_000_000);
+        let bob = deposit(&mut sp, 1_000_000_000);
+
+        // 400 WUSD of debt against 5 units of collateral
+        absorb_debt(&mut sp, 400_000_000, 5_000_000_000).unwrap();
+
+        assert_eq!(sp.total_deposits, 3_600_000_000);
+        assert!(compounded_deposit(&alice, &sp).unwrap().abs_diff(2_700_000_000) <= 1);
+        assert!(compounded_deposit(&bob, &sp).unwrap().abs_diff(900_000_000) <= 1);
+        assert!(collateral_gain(&alice, &sp).unwrap().abs_diff(3_750_000_000) <= 1);
+        assert!(collateral_gain(&bob, &sp).unwrap().abs_diff(1_250_000_000) <= 1);
+    }
+
+    #[test]
+    fn later_depositors_do_not_share_earlier_gains() {
+        let mut sp = test_utils::stability_pool(Pubkey::default());
+        let alice = deposit(&mut sp, 1_000_000_000);
+        absorb_debt(&mut sp, 500_000_000, 1_000_000).unwrap();
+
+        let bob = deposit(&mut sp, 500_000_000);
+        absorb_debt(&mut sp, 500_000_000, 1_000_000).unwrap();
+
+        assert!(compounded_deposit(&alice, &sp).unwrap().abs_diff(250_000_000) <= 1);
+        assert!(compounded_deposit(&bob, &sp).unwrap().abs_diff(250_000_000) <= 1);
+        assert!(collateral_gain(&alice, &sp).unwrap().abs_diff(1_500_000) <= 1);
+        assert!(collateral_gain(&bob, &sp).unwrap().abs_diff(500_000) <= 1);
+    }
+
+    #[test]
+    fn scale_changes_keep_tracking_deposits() {
+        let mut sp = test_utils::stability_pool(Pubkey::default());
+        let alice = deposit(&mut sp, 10_000_000_000_000_000);
+
+        // wipe out almost everything, pushing P below the scale factor
+        absorb_debt(&mut sp, 10_000_000_000_000_000 - 1_000_000, 1_000).unwrap();
+        assert_eq!(sp.current_scale, 1);
+
+        let remaining = compounded_deposit(&alice, &sp).unwrap();
+        assert!(remaining.abs_diff(1_000_000) <= 1);
+        assert!(collateral_gain(&alice, &sp).unwrap().abs_diff(1_000) <= 1);
+        assert_eq!(stability_pool_capacity(&sp), 0);
+    }
+
+    #[test]
+    fn liquidations_fall_back_to_the_liquidator_once_scales_run_out() {
+        let mut sp = test_utils::stability_pool(Pubkey::default());
+        deposit(&mut sp, 4_000_000);
+
+        let (absorbed, debt, collateral) = absorb_liquidation(Some(sp.clone()), 6_000_000, 600).unwrap().unwrap();
+        assert_eq!((debt, collateral), (3_000_000, 300));
+        assert_eq!(absorbed.total_deposits, 1_000_000);
+
+        // P is one rescale away from needing a scale the pool doesn't have
+        sp.current_scale = MAX_STABILITY_POOL_SCALES as u64 - 1;
+        sp.scale_sums = vec![0; MAX_STABILITY_POOL_SCALES];
+        sp.p = STABILITY_POOL_SCALE_FACTOR * 2;
+        assert!(absorb_liquidation(Some(sp.clone()), 6_000_000, 600).unwrap().is_none());
+        assert!(absorb_liquidation(Some(sp), 1_000, 600).unwrap().is_some());
+
+        assert!(absorb_liquidation(None, 6_000_000, 600).unwrap().is_none());
+    }
+
+    #[test]
+    fn open_deposits_count_non_zero_snapshots() {
+        let mut sp = test_utils::stability_pool(Pubkey::default());
+        let mut alice = deposit(&mut sp, 1_000_000_000);
+        let mut bob = deposit(&mut sp, 1_000_000_000);
+        assert_eq!(sp.open_deposits, 2);
+
+        snapshot_deposit(&mut alice, &mut sp, 2_000_000_000);
+        assert_eq!(sp.open_deposits, 2);
+
+        snapshot_deposit(&mut alice, &mut sp, 0);
+        snapshot_deposit(&mut bob, &mut sp, 0);
+        assert_eq!(sp.open_deposits, 0);
+    }
+}
@@ -33,7 +33,7 @@ fi
 echo ""
 echo -e "${YELLOW}2️⃣  Building anchor program...${NC}"
 cd /home/kresn/wusd/anchor
-anchor build 2>&1 | tail -3
+anchor build -- --features mock-oracle 2>&1 | tail -3
 echo -e "${GREEN}   ✅ Program built${NC}"
 
 # Step 3: Deploy to devnet
@@ -38,7 +38,7 @@ echo -e "${GREEN}   ✅ Validator running: $(solana cluster-version)${NC}"
 echo ""
 echo -e "${YELLOW}3️⃣  Building and deploying anchor program...${NC}"
 cd /home/kresn/wusd/anchor
-anchor build 2>&1 | tail -3
+anchor build -- --features mock-oracle 2>&1 | tail -3
 anchor deploy 2>&1 | tail -3
 echo -e "${GREEN}   ✅ Program built and deployed${NC}"
 